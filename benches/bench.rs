@@ -6,7 +6,7 @@ criterion_main!(benches);
 mod sequence {
     use criterion::{black_box, BatchSize, Bencher, Criterion};
     use rand::rngs::OsRng;
-    use rand_unique::RandomSequence;
+    use rand_unique::{Backend, QuadraticResidue, RandomSequence, RandomSequenceBuilder};
 
     pub fn sequence_bench(c: &mut Criterion) {
         let mut group = c.benchmark_group("sequence");
@@ -15,6 +15,41 @@ mod sequence {
         group.bench_function("n_u32", bench_n_u32);
         group.bench_function("n_u64", bench_n_u64);
         group.bench_function("n_usize", bench_n_usize);
+        group.bench_function("n_u128", bench_n_u128);
+        group.bench_function("n_u128_domain", bench_n_u128_domain);
+        group.bench_function("n_u128_small_prime", bench_n_u128_small_prime);
+        group.bench_function("n_u8_table", bench_n_u8_table);
+        group.bench_function("n_u16_table", bench_n_u16_table);
+        group.bench_function("n_u64_4_rounds", bench_n_u64_4_rounds);
+        group.bench_function("n_u64_8_rounds", bench_n_u64_8_rounds);
+        group.bench_function("index_of_u8", bench_index_of_u8);
+        group.bench_function("index_of_u16", bench_index_of_u16);
+        group.bench_function("index_of_u32", bench_index_of_u32);
+        group.bench_function("index_of_u64", bench_index_of_u64);
+        group.bench_function("index_of_usize", bench_index_of_usize);
+        group.bench_function("index_of_u128", bench_index_of_u128);
+        group.bench_function("index_of_u128_domain", bench_index_of_u128_domain);
+        group.bench_function("index_of_u128_small_prime", bench_index_of_u128_small_prime);
+        group.bench_function("sqrt_mod_u32", bench_sqrt_mod_u32);
+        group.bench_function("sqrt_mod_u32_division", bench_sqrt_mod_u32_division);
+        group.bench_function("sqrt_mod_u64", bench_sqrt_mod_u64);
+        group.bench_function("sqrt_mod_u64_division", bench_sqrt_mod_u64_division);
+        group.bench_function("n_u32_feistel", bench_n_u32_feistel);
+        group.bench_function("n_u64_feistel", bench_n_u64_feistel);
+        group.bench_function("n_u128_feistel", bench_n_u128_feistel);
+        #[cfg(feature = "ff1")]
+        group.bench_function("n_u64_ff1", bench_n_u64_ff1);
+        group.bench_function("n_loop_u16_1024", bench_n_loop_u16);
+        group.bench_function("fill_u16_1024", bench_fill_u16);
+        group.bench_function("n_loop_u32_1024", bench_n_loop_u32);
+        group.bench_function("fill_u32_1024", bench_fill_u32);
+        group.bench_function("n_loop_u64_1024", bench_n_loop_u64);
+        group.bench_function("fill_u64_1024", bench_fill_u64);
+        group.bench_function("iter_u16_1024", bench_iter_u16);
+        group.bench_function("iter_u32_1024", bench_iter_u32);
+        group.bench_function("iter_u64_1024", bench_iter_u64);
+        group.bench_function("iter_u128_1024", bench_iter_u128);
+        group.bench_function("iter_rev_u64_1024", bench_iter_rev_u64);
         group.bench_function("rand_u64", bench_rand_u64);
     }
 
@@ -37,6 +72,250 @@ mod sequence {
     bench_n!(bench_n_u32, u32);
     bench_n!(bench_n_u64, u64);
     bench_n!(bench_n_usize, usize);
+    bench_n!(bench_n_u128, u128);
+
+    /// A u128 domain, with a prime below `2^100` rather than `2^128`.
+    const U128_DOMAIN: u128 = 10u128.pow(30);
+
+    /// The prime `2^126 + 7`, which a spec can set for the full u128 range, and which is too far
+    /// from a power of two to reduce by folding.
+    const U128_SMALL_PRIME: u128 = 85070591730234615865843651857942052871;
+
+    fn bench_n_u128_domain(b: &mut Bencher) {
+        let sequence = RandomSequenceBuilder::<u128>::rand(&mut OsRng).with_domain(U128_DOMAIN).into_iter();
+
+        b.iter_batched(
+            || rand::random::<u128>() % U128_DOMAIN,
+            |index| black_box(sequence.n(index)),
+            BatchSize::SmallInput,
+        );
+    }
+
+    fn bench_n_u128_small_prime(b: &mut Bencher) {
+        let config = RandomSequenceBuilder::<u128>::rand(&mut OsRng);
+        let sequence = RandomSequenceBuilder { prime: U128_SMALL_PRIME, ..config }.into_iter();
+
+        b.iter_batched(
+            rand::random::<u128>,
+            |index| black_box(sequence.n(index)),
+            BatchSize::SmallInput,
+        );
+    }
+
+    macro_rules! bench_n_table {
+        ($name:ident, $type:ident, $len:literal) => {
+            fn $name(b: &mut Bencher) {
+                let sequence = RandomSequence::<$type>::rand(&mut OsRng);
+                let (mut forward, mut inverse) = (vec![0; $len], vec![0; $len]);
+                let sequence = sequence.materialize_into((&mut forward[..]).try_into().unwrap(), (&mut inverse[..]).try_into().unwrap());
+
+                b.iter_batched(
+                    || rand::random::<$type>(),
+                    |index| black_box({ sequence.n(index) }),
+                    BatchSize::SmallInput,
+                );
+            }
+        };
+    }
+
+    bench_n_table!(bench_n_u8_table, u8, 256);
+    bench_n_table!(bench_n_u16_table, u16, 65536);
+
+    macro_rules! bench_n_rounds {
+        ($name:ident, $type:ident, $rounds:literal) => {
+            fn $name(b: &mut Bencher) {
+                let sequence = RandomSequenceBuilder::<$type>::rand(&mut OsRng).with_rounds($rounds).into_iter();
+
+                b.iter_batched(
+                    || rand::random::<$type>(),
+                    |index| black_box({ sequence.n(index) }),
+                    BatchSize::SmallInput,
+                );
+            }
+        };
+    }
+
+    bench_n_rounds!(bench_n_u64_4_rounds, u64, 4);
+    bench_n_rounds!(bench_n_u64_8_rounds, u64, 8);
+
+    /// Inverting the sequence takes a modular exponentiation, so is dominated by the modular
+    /// reduction in `QuadraticResidue::mul_mod`.
+    macro_rules! bench_index_of {
+        ($name:ident, $type:ident) => {
+            fn $name(b: &mut Bencher) {
+                let sequence = RandomSequence::<$type>::rand(&mut OsRng);
+
+                b.iter_batched(
+                    || rand::random::<$type>(),
+                    |value| black_box({ sequence.index_of(value) }),
+                    BatchSize::SmallInput,
+                );
+            }
+        };
+    }
+
+    bench_index_of!(bench_index_of_u8, u8);
+    bench_index_of!(bench_index_of_u16, u16);
+    bench_index_of!(bench_index_of_u32, u32);
+    bench_index_of!(bench_index_of_u64, u64);
+    bench_index_of!(bench_index_of_usize, usize);
+    bench_index_of!(bench_index_of_u128, u128);
+
+    fn bench_index_of_u128_domain(b: &mut Bencher) {
+        let sequence = RandomSequenceBuilder::<u128>::rand(&mut OsRng).with_domain(U128_DOMAIN).into_iter();
+
+        b.iter_batched(
+            || rand::random::<u128>() % U128_DOMAIN,
+            |value| black_box(sequence.index_of(value)),
+            BatchSize::SmallInput,
+        );
+    }
+
+    fn bench_index_of_u128_small_prime(b: &mut Bencher) {
+        let config = RandomSequenceBuilder::<u128>::rand(&mut OsRng);
+        let sequence = RandomSequenceBuilder { prime: U128_SMALL_PRIME, ..config }.into_iter();
+
+        b.iter_batched(
+            rand::random::<u128>,
+            |value| black_box(sequence.index_of(value)),
+            BatchSize::SmallInput,
+        );
+    }
+
+    /// The square root `y^((prime + 1) / 4) mod prime` that dominates `index_of`, comparing the
+    /// division free `QuadraticResidue::mul_mod` with the double width division it replaced.
+    macro_rules! bench_sqrt_mod {
+        ($name:ident, $type:ident, |$a:ident, $b:ident, $prime:ident| $mul_mod:expr) => {
+            fn $name(b: &mut Bencher) {
+                let prime = black_box(RandomSequenceBuilder::<$type>::new(0, 0).prime);
+                let mul_mod = |$a: $type, $b: $type, $prime: $type| $mul_mod;
+
+                b.iter_batched(
+                    || rand::random::<$type>() % prime,
+                    |y| {
+                        let (mut exponent, mut base, mut result) = ((prime >> 2) + 1, y, 1);
+                        while exponent > 0 {
+                            if exponent & 1 == 1 {
+                                result = mul_mod(result, base, prime);
+                            }
+                            base = mul_mod(base, base, prime);
+                            exponent >>= 1;
+                        }
+                        black_box(result)
+                    },
+                    BatchSize::SmallInput,
+                );
+            }
+        };
+    }
+
+    bench_sqrt_mod!(bench_sqrt_mod_u32, u32, |a, b, prime| a.mul_mod(b, prime));
+    bench_sqrt_mod!(bench_sqrt_mod_u32_division, u32, |a, b, prime| (a as u64 * b as u64 % prime as u64) as u32);
+    bench_sqrt_mod!(bench_sqrt_mod_u64, u64, |a, b, prime| a.mul_mod(b, prime));
+    bench_sqrt_mod!(bench_sqrt_mod_u64_division, u64, |a, b, prime| (a as u128 * b as u128 % prime as u128) as u64);
+
+    macro_rules! bench_n_feistel {
+        ($name:ident, $type:ident) => {
+            fn $name(b: &mut Bencher) {
+                let backend = Backend::Feistel { rounds: 8 };
+                let sequence = RandomSequenceBuilder::<$type>::rand(&mut OsRng).with_backend(backend).into_iter();
+
+                b.iter_batched(
+                    || rand::random::<$type>(),
+                    |index| black_box({ sequence.n(index) }),
+                    BatchSize::SmallInput,
+                );
+            }
+        };
+    }
+
+    bench_n_feistel!(bench_n_u32_feistel, u32);
+    bench_n_feistel!(bench_n_u64_feistel, u64);
+    bench_n_feistel!(bench_n_u128_feistel, u128);
+
+    /// The cryptographically strong FF1 backend, with an AES-256 key.
+    #[cfg(feature = "ff1")]
+    fn bench_n_u64_ff1(b: &mut Bencher) {
+        let key = rand_unique::Ff1Key::Aes256(rand::random());
+        let sequence = rand_unique::Ff1Sequence::<u64>::new(key);
+
+        b.iter_batched(
+            rand::random::<u64>,
+            |index| black_box(sequence.n(index)),
+            BatchSize::SmallInput,
+        );
+    }
+
+    macro_rules! bench_fill {
+        ($loop_name:ident, $fill_name:ident, $type:ident) => {
+            /// A block of 1024 consecutive values, one call to `n` at a time.
+            fn $loop_name(b: &mut Bencher) {
+                let sequence = RandomSequence::<$type>::rand(&mut OsRng);
+                let mut values = vec![0; 1024];
+
+                b.iter_batched(
+                    || rand::random::<$type>(),
+                    |start| {
+                        for (i, value) in values.iter_mut().enumerate() {
+                            *value = sequence.n(start.wrapping_add(i as $type));
+                        }
+                        black_box(&values);
+                    },
+                    BatchSize::SmallInput,
+                );
+            }
+
+            /// The same block of 1024 consecutive values, with the batch API.
+            fn $fill_name(b: &mut Bencher) {
+                let sequence = RandomSequence::<$type>::rand(&mut OsRng);
+                let mut values = vec![0; 1024];
+
+                b.iter_batched(
+                    || rand::random::<$type>(),
+                    |start| {
+                        sequence.fill(&mut values, start);
+                        black_box(&values);
+                    },
+                    BatchSize::SmallInput,
+                );
+            }
+        };
+    }
+
+    bench_fill!(bench_n_loop_u16, bench_fill_u16, u16);
+    bench_fill!(bench_n_loop_u32, bench_fill_u32, u32);
+    bench_fill!(bench_n_loop_u64, bench_fill_u64, u64);
+
+    macro_rules! bench_iter {
+        ($name:ident, $type:ident, $step:ident) => {
+            /// The same block of 1024 consecutive values, walking the sequence from a random index.
+            fn $name(b: &mut Bencher) {
+                let sequence = RandomSequence::<$type>::rand(&mut OsRng);
+                let mut values = vec![0; 1024];
+
+                b.iter_batched(
+                    || {
+                        let mut sequence = sequence.clone();
+                        sequence.set_index(rand::random::<$type>());
+                        sequence
+                    },
+                    |mut sequence| {
+                        for value in values.iter_mut() {
+                            *value = sequence.$step();
+                        }
+                        black_box(&values);
+                    },
+                    BatchSize::SmallInput,
+                );
+            }
+        };
+    }
+
+    bench_iter!(bench_iter_u16, u16, wrapping_next);
+    bench_iter!(bench_iter_u32, u32, wrapping_next);
+    bench_iter!(bench_iter_u64, u64, wrapping_next);
+    bench_iter!(bench_iter_u128, u128, wrapping_next);
+    bench_iter!(bench_iter_rev_u64, u64, wrapping_prev);
 
     /// Compare standard random number generation time.
     fn bench_rand_u64(b: &mut Bencher) {
@@ -2,23 +2,27 @@ use std::error::Error;
 
 use plotters::prelude::*;
 use rand::rngs::OsRng;
-use rand_unique::RandomSequence;
+use rand_unique::{Backend, RandomSequenceBuilder};
 
-/// Generate histogram and scatter plots for a u16 sequence to demonstrate uniformity.
+/// Generate histogram and scatter plots for a u16 sequence to demonstrate uniformity, for both the
+/// default quadratic residue backend and the Feistel backend.
 fn main() -> Result<(), Box<dyn Error>> {
-    plot_histogram("charts/histogram-u16.png", 50, 5000)?;
-    plot_scatter("charts/scatter-u16.png", 1000)?;
+    let feistel = Backend::Feistel { rounds: 8 };
+    plot_histogram("charts/histogram-u16.png", Backend::QuadraticResidue, 50, 5000)?;
+    plot_scatter("charts/scatter-u16.png", Backend::QuadraticResidue, 1000)?;
+    plot_histogram("charts/histogram-u16-feistel.png", feistel, 50, 5000)?;
+    plot_scatter("charts/scatter-u16-feistel.png", feistel, 1000)?;
     Ok(())
 }
 
 /// Plot the distribution of sequence outputs.
-fn plot_histogram(file_name: &str, bins: u32, count: u32) -> Result<(), Box<dyn Error>> {
+fn plot_histogram(file_name: &str, backend: Backend, bins: u32, count: u32) -> Result<(), Box<dyn Error>> {
     type T = u16;
 
     let root = BitMapBackend::new(file_name, (1280, 960)).into_drawing_area();
     root.fill(&WHITE)?;
 
-    let sequence = RandomSequence::<T>::rand(&mut OsRng);
+    let sequence = RandomSequenceBuilder::<T>::rand(&mut OsRng).with_backend(backend).into_iter();
     let data: Vec<T> = sequence.take(count as usize).collect();
     let binned: Vec<(u32, u32)> =
         data.iter().map(|x| ((*x as f64 / (T::MAX as f64 / bins as f64)) as u32, 1)).collect();
@@ -41,20 +45,20 @@ fn plot_histogram(file_name: &str, bins: u32, count: u32) -> Result<(), Box<dyn
 
     chart.draw_series(Histogram::vertical(&chart).style(RED.filled()).data(binned))?;
 
-    root.present().expect(&format!("Unable to write result to file {file_name}"));
+    root.present().unwrap_or_else(|_| panic!("Unable to write result to file {file_name}"));
     println!("Histogram has been saved to {file_name}");
 
     Ok(())
 }
 
 /// Plot the outputs in a scatter.
-fn plot_scatter(file_name: &str, count: u32) -> Result<(), Box<dyn Error>> {
+fn plot_scatter(file_name: &str, backend: Backend, count: u32) -> Result<(), Box<dyn Error>> {
     type T = u16;
 
     let root = BitMapBackend::new(file_name, (1280, 960)).into_drawing_area();
     root.fill(&WHITE)?;
 
-    let sequence = RandomSequence::<T>::rand(&mut OsRng);
+    let sequence = RandomSequenceBuilder::<T>::rand(&mut OsRng).with_backend(backend).into_iter();
     let data: Vec<(u32, u32)> = sequence.take(count as usize).enumerate().map(|(i, o)| (i as u32, o as u32)).collect();
 
     let mut chart = ChartBuilder::on(&root)
@@ -73,9 +77,9 @@ fn plot_scatter(file_name: &str, count: u32) -> Result<(), Box<dyn Error>> {
         .draw()
         .unwrap();
 
-    chart.draw_series(data.iter().map(|point| Circle::new(*point, 2, &BLUE)))?;
+    chart.draw_series(data.iter().map(|point| Circle::new(*point, 2, BLUE)))?;
 
-    root.present().expect(&format!("Unable to write result to file {file_name}"));
+    root.present().unwrap_or_else(|_| panic!("Unable to write result to file {file_name}"));
     println!("Scatter has been saved to {file_name}");
 
     Ok(())
@@ -0,0 +1,238 @@
+use crate::builder::{QuadraticResidue, RandomSequenceBuilder};
+use crate::permutation::{BuilderPermutation, Permutation};
+use crate::sequence::RandomSequence;
+use crate::signed::SequenceInt;
+
+/// A deterministic pseudo-random sequence of unique `BITS`-bit numbers, for any width from 1 to
+/// 128 bits, such as 24-bit RGB colours or 48-bit MAC addresses.
+///
+/// Values are stored in the smallest unsigned integer with at least `BITS` bits, the
+/// [BitWidth::Storage] of [Bits], so a `RandomBitsSequence<24>` generates `u32` values in
+/// `0..2^24`. The permutation runs directly over the `2^BITS` values with the prime for the width,
+/// so unlike a domain of the storage type, no values are cycle-walked.
+///
+/// ```rust
+/// use rand_unique::RandomBitsSequence;
+///
+/// let sequence = RandomBitsSequence::<24>::new(10, 2);
+/// let colour: u32 = sequence.n(0);
+/// assert!(colour < 1 << 24);
+/// assert_eq!(sequence.index_of(colour), 0);
+/// ```
+pub type RandomBitsSequence<const BITS: u32> =
+    RandomSequence<<Bits<BITS> as BitWidth>::Storage, BitsPermutation<<Bits<BITS> as BitWidth>::Storage, BITS>>;
+
+/// A bit width from 1 to 128, selecting the [BitWidth::Storage] type of a [RandomBitsSequence].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bits<const BITS: u32>;
+
+/// The storage of the values of a [Bits] width.
+pub trait BitWidth {
+    /// The smallest unsigned integer type with at least `BITS` bits, such as `u32` for 24 bits.
+    type Storage: QuadraticResidue + SequenceInt<Unsigned = Self::Storage>;
+
+    /// Initialise a [RandomSequenceBuilder] of the values `0..2^BITS` from a specific seed pair.
+    fn builder(seed_base: Self::Storage, seed_offset: Self::Storage) -> RandomSequenceBuilder<Self::Storage>;
+}
+
+macro_rules! impl_bit_width {
+    ($storage:ident, [$($bits:literal),+]) => {
+        $(
+            impl BitWidth for Bits<$bits> {
+                type Storage = $storage;
+
+                #[inline]
+                fn builder(seed_base: $storage, seed_offset: $storage) -> RandomSequenceBuilder<$storage> {
+                    RandomSequenceBuilder::<$storage>::new(seed_base, seed_offset).with_bits($bits)
+                }
+            }
+        )+
+    };
+}
+
+impl_bit_width!(u8, [1, 2, 3, 4, 5, 6, 7, 8]);
+impl_bit_width!(u16, [9, 10, 11, 12, 13, 14, 15, 16]);
+impl_bit_width!(u32, [17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32]);
+impl_bit_width!(u64, [
+    33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61,
+    62, 63, 64
+]);
+impl_bit_width!(u128, [
+    65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 89, 90, 91, 92, 93,
+    94, 95, 96, 97, 98, 99, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110, 111, 112, 113, 114, 115, 116, 117,
+    118, 119, 120, 121, 122, 123, 124, 125, 126, 127, 128
+]);
+
+/// The [Permutation] of a [RandomBitsSequence], the [BuilderPermutation] of a [RandomSequenceBuilder]
+/// restricted to `BITS` bits.
+#[derive(Debug, Clone)]
+pub struct BitsPermutation<U, const BITS: u32>
+where
+    U: QuadraticResidue
+{
+    permutation: BuilderPermutation<U>,
+}
+
+impl<U, const BITS: u32> BitsPermutation<U, BITS>
+where
+    U: QuadraticResidue
+{
+    /// The config/builder of the wrapped permutation, see [BuilderPermutation::config].
+    #[inline]
+    pub fn config(&self) -> &RandomSequenceBuilder<U> {
+        &self.permutation.config
+    }
+}
+
+impl<U, const BITS: u32> Permutation<U> for BitsPermutation<U, BITS>
+where
+    U: QuadraticResidue + SequenceInt<Unsigned = U>
+{
+    #[inline]
+    fn max_index(&self) -> U {
+        Permutation::<U>::max_index(&self.permutation)
+    }
+
+    #[inline(always)]
+    fn apply(&self, index: U) -> U {
+        Permutation::<U>::apply(&self.permutation, index)
+    }
+
+    #[inline(always)]
+    fn apply_sequential(&mut self, index: U) -> U {
+        Permutation::<U>::apply_sequential(&mut self.permutation, index)
+    }
+
+    #[inline]
+    fn invert(&self, value: U) -> Option<U> {
+        Permutation::<U>::invert(&self.permutation, value)
+    }
+
+    #[inline]
+    fn contains(&self, value: U) -> bool {
+        Permutation::<U>::contains(&self.permutation, value)
+    }
+
+    #[inline]
+    fn apply_many(&self, indices: &[U], values: &mut [U]) {
+        Permutation::<U>::apply_many(&self.permutation, indices, values)
+    }
+}
+
+impl<U, const BITS: u32> RandomSequence<U, BitsPermutation<U, BITS>>
+where
+    U: QuadraticResidue + SequenceInt<Unsigned = U>,
+    Bits<BITS>: BitWidth<Storage = U>,
+{
+    /// Initialise a random sequence of `BITS`-bit values from the seeds.
+    ///
+    /// Only the low `BITS` bits of each seed are used.
+    pub fn new(seed_base: U, seed_offset: U) -> Self {
+        Bits::<BITS>::builder(seed_base, seed_offset).into()
+    }
+}
+
+impl<U, const BITS: u32> From<RandomSequenceBuilder<U>> for RandomSequence<U, BitsPermutation<U, BITS>>
+where
+    U: QuadraticResidue + SequenceInt<Unsigned = U>,
+    Bits<BITS>: BitWidth<Storage = U>,
+{
+    /// Generate the sequence of a builder restricted to `BITS` bits, such as a deserialized
+    /// [BitsPermutation::config].
+    ///
+    /// # Panics
+    ///
+    /// If the builder doesn't generate the values `0..2^BITS`, see [RandomSequenceBuilder::with_bits].
+    fn from(config: RandomSequenceBuilder<U>) -> Self {
+        let max_index = U::max_value() >> (U::zero().count_zeros() - BITS) as usize;
+        assert!(
+            config.max_index() == max_index && config.range_start == U::zero() && config.range_step == U::one(),
+            "The builder must generate the values of {} bits.",
+            BITS,
+        );
+        RandomSequence::from_permutation(BitsPermutation {
+            permutation: BuilderPermutation::from(config),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::vec::Vec;
+
+    use super::*;
+
+    macro_rules! test_bits {
+        ($name:ident, $bits:literal, $storage:ident, $check:expr) => {
+            #[test]
+            fn $name() {
+                let sequence = RandomBitsSequence::<$bits>::new(7, 11);
+                let max: $storage = $storage::MAX >> ($storage::BITS - $bits);
+                assert_eq!(Permutation::<$storage>::max_index(&sequence.permutation), max);
+
+                // check the values are unique, fit in the width, and invert
+                let nums: Vec<$storage> = sequence.clone().take($check).collect();
+                assert_eq!(nums.iter().copied().collect::<HashSet<$storage>>().len(), nums.len());
+                for (i, num) in nums.iter().enumerate() {
+                    assert!(*num <= max, "{} doesn't fit in {} bits", num, $bits);
+                    assert_eq!(sequence.n(i as $storage), *num);
+                    assert_eq!(sequence.index_of(*num), i as $storage);
+                }
+                if (max as u128).checked_add(1) == Some($check as u128) {
+                    assert_eq!(nums.len(), $check);
+                    assert!(!sequence.contains(max.wrapping_add(1)) || $bits == $storage::BITS);
+                }
+
+                // check the sequence matches the builder, and restores from the serializable config
+                let config = RandomSequenceBuilder::<$storage>::new(7, 11).with_bits($bits);
+                let builder_sequence = config.into_iter();
+                assert!((0..100).all(|i| sequence.n(i) == builder_sequence.n(i)));
+                let restored = RandomBitsSequence::<$bits>::from(*sequence.permutation.config());
+                assert!((0..100).all(|i| sequence.n(i) == restored.n(i)));
+
+                // check the batches of the builder's permutation match n
+                let indices: Vec<$storage> = (0..100).map(|i: $storage| i.wrapping_mul(97) & max).collect();
+                let mut values = std::vec![0; indices.len()];
+                sequence.n_many(&indices, &mut values);
+                assert!(indices.iter().zip(&values).all(|(i, value)| sequence.n(*i) == *value));
+                sequence.fill(&mut values, max);
+                assert!(values.iter().enumerate().all(|(i, value)| sequence.n(max.wrapping_add(i as $storage) & max) == *value));
+            }
+        };
+    }
+
+    test_bits!(test_1_bits, 1, u8, 2);
+    test_bits!(test_2_bits, 2, u8, 4);
+    test_bits!(test_5_bits, 5, u8, 32);
+    test_bits!(test_8_bits, 8, u8, 256);
+    test_bits!(test_12_bits, 12, u16, 4096);
+    test_bits!(test_16_bits, 16, u16, 65536);
+    test_bits!(test_24_bits, 24, u32, 100_000);
+    test_bits!(test_32_bits, 32, u32, 100_000);
+    test_bits!(test_40_bits, 40, u64, 100_000);
+    test_bits!(test_48_bits, 48, u64, 100_000);
+    test_bits!(test_64_bits, 64, u64, 10_000);
+    test_bits!(test_100_bits, 100, u128, 10_000);
+    test_bits!(test_128_bits, 128, u128, 10_000);
+
+    #[test]
+    fn test_bits_domain() {
+        // a bit width is the same sequence as the equivalent domain, without cycle-walking
+        let sequence = RandomBitsSequence::<24>::new(7, 11);
+        let domain = RandomSequenceBuilder::<u32>::new(7, 11).with_domain(1 << 24).into_iter();
+        assert!((0..1000).all(|i| sequence.n(i) == domain.n(i)));
+    }
+
+    #[test]
+    #[should_panic(expected = "The builder must generate the values of 24 bits.")]
+    fn test_bits_from_wrong_builder() {
+        let _ = RandomBitsSequence::<24>::from(RandomSequenceBuilder::<u32>::new(7, 11).with_bits(20));
+    }
+
+    #[test]
+    #[should_panic(expected = "The bit width must be from 1 to 32 bits.")]
+    fn test_with_bits_too_wide() {
+        let _ = RandomSequenceBuilder::<u32>::new(7, 11).with_bits(33);
+    }
+}
@@ -1,6 +1,13 @@
-use num_traits::{AsPrimitive, PrimInt, WrappingAdd, WrappingSub};
+use core::ops::RangeInclusive;
 
+use num_traits::{FromPrimitive, PrimInt, WrappingAdd, WrappingSub};
+
+use crate::constant::Const;
+use crate::error::Error;
+use crate::feistel;
+use crate::primes::{is_prime, prime_below};
 use crate::sequence::RandomSequence;
+use crate::signed::SequenceInt;
 
 /// The configuration for [RandomSequence], a random unique sequence generator.
 ///
@@ -13,12 +20,19 @@ use crate::sequence::RandomSequence;
 ///
 /// Crate versioning will bump:
 /// - _Minor version_: when the hard coded parameters are updated in favour of better ones. It is
-///    safe to serialize the [RandomSequenceBuilder] between minor versions.
+///   safe to serialize the [RandomSequenceBuilder] between minor versions.
 /// - _Major version_: when the sequence generation logic fundamentally changes the sequence,
-///    meaning it would be potentially unsafe to serialize the [RandomSequenceBuilder] between
-///    major crate version changes.
+///   meaning it would be potentially unsafe to serialize the [RandomSequenceBuilder] between
+///   major crate version changes.
+///
+/// Deserialization checks the parameters with [RandomSequenceBuilder::validate], so a corrupted or
+/// tampered config fails to deserialize rather than generating duplicate values.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(remote = "Self", bound(deserialize = "T: serde::Deserialize<'de>"))
+)]
 pub struct RandomSequenceBuilder<T>
 where
     T: QuadraticResidue
@@ -41,17 +55,176 @@ where
     pub prime: T,
 
     /// A value that provides some noise from the xor to generate a pseudo-uniform distribution.
+    ///
+    /// Used as is by [KeySchedule::Legacy], and mixed with the seeds by [KeySchedule::Seeded].
     pub intermediate_xor: T,
+
+    /// The number of values in the sequence, which will generate values in `0..domain`. A `None`
+    /// domain covers the entire range of `T`.
+    ///
+    /// Set with [RandomSequenceBuilder::with_domain], which also updates the `prime`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub domain: Option<T>,
+
+    /// The first value of the range the sequence is mapped onto, where the sequence outputs
+    /// `range_start + range_step * x` for each `x` in the domain.
+    ///
+    /// Set with [RandomSequenceBuilder::with_range] or [RandomSequenceBuilder::with_range_step].
+    #[cfg_attr(feature = "serde", serde(default = "num_traits::Zero::zero"))]
+    pub range_start: T,
+
+    /// The stride between values of the range the sequence is mapped onto.
+    #[cfg_attr(feature = "serde", serde(default = "num_traits::One::one"))]
+    pub range_step: T,
+
+    /// The permutation used to generate the sequence, see [Backend].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub backend: Backend,
+
+    /// The number of residue passes of the [Backend::QuadraticResidue] permutation, where each pass
+    /// after the first mixes in an offset and xor derived from the seeds. Defaults to 2.
+    ///
+    /// Set with [RandomSequenceBuilder::with_rounds].
+    #[cfg_attr(feature = "serde", serde(default = "default_rounds"))]
+    pub rounds: u8,
+
+    /// How the offset and xor of the [Backend::QuadraticResidue] rounds are derived from the seeds,
+    /// see [KeySchedule]. Defaults to [KeySchedule::Legacy], which builders serialized before the
+    /// field existed also deserialize as, and so keep generating the same sequence.
+    ///
+    /// Set with [RandomSequenceBuilder::with_key_schedule].
+    #[cfg_attr(feature = "serde", serde(default = "legacy_key_schedule"))]
+    pub key_schedule: KeySchedule,
+
+    /// Whether each [Backend::QuadraticResidue] pass also scrambles the values `>= prime`, which the
+    /// quadratic residue maps to themselves, with a second residue over a small window at the top
+    /// of the range. Defaults to `false`, which builders serialized before the field existed also
+    /// deserialize as, and so keep generating the same sequence.
+    ///
+    /// Set with [RandomSequenceBuilder::with_fold_tail].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fold_tail: bool,
+}
+
+/// The default number of [RandomSequenceBuilder::rounds].
+const DEFAULT_ROUNDS: u8 = 2;
+
+#[cfg(feature = "serde")]
+fn default_rounds() -> u8 {
+    DEFAULT_ROUNDS
+}
+
+#[cfg(feature = "serde")]
+fn legacy_key_schedule() -> KeySchedule {
+    KeySchedule::Legacy
+}
+
+/// How a [RandomSequenceBuilder] derives the round keys of the [Backend::QuadraticResidue]
+/// permutation from its seeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeySchedule {
+    /// The original schedule, where `intermediate_offset` is derived from `seed_offset` alone and
+    /// `intermediate_xor` is a fixed constant.
+    ///
+    /// `seed_base` only sets the start index, so builders which differ only by `seed_base` generate
+    /// rotations of the same cycle. The default, so the same seeds keep generating the same
+    /// sequence as earlier versions of the crate.
+    Legacy,
+
+    /// Derives both `intermediate_offset` and `intermediate_xor` from both seeds, so every seed
+    /// pair generates a distinct permutation rather than a rotation of another one. Opt in with
+    /// [RandomSequenceBuilder::with_key_schedule].
+    Seeded,
+}
+
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for RandomSequenceBuilder<T>
+where
+    T: QuadraticResidue + serde::Serialize
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RandomSequenceBuilder::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for RandomSequenceBuilder<T>
+where
+    T: QuadraticResidue + serde::Deserialize<'de>
+{
+    /// Deserialize a builder, rejecting any parameters that fail [RandomSequenceBuilder::validate].
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let config = RandomSequenceBuilder::deserialize(deserializer)?;
+        config.validate().map_err(serde::de::Error::custom)?;
+        Ok(config)
+    }
+}
+
+/// The permutation function used by a [RandomSequenceBuilder] to scramble the sequence index.
+///
+/// All backends generate the sequence in O(1) time and are invertible, and so support the same
+/// [RandomSequence] API. Compare the two with the `sequence` benchmarks in `benches/bench.rs`. See
+/// the [Ff1Sequence](crate::Ff1Sequence) of the `ff1` feature for a cryptographically strong
+/// sequence, keyed by an AES key rather than the seeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Backend {
+    /// The default permutation, `qpr(qpr(index + intermediate_offset) ^ intermediate_xor)` for the
+    /// quadratic prime residue `qpr(x) = x^2 mod prime`.
+    #[default]
+    QuadraticResidue,
+
+    /// A keyed Feistel network over the bits of the sequence domain, with a 64-bit key derived
+    /// from every bit of both seeds.
+    ///
+    /// The network is balanced for the even bit widths of each integer type, and unbalanced for
+    /// domains with an odd number of bits. Each round costs one 64-bit mixing function, and at
+    /// least 4 rounds are recommended for a well mixed output.
+    Feistel {
+        /// The number of Feistel rounds.
+        rounds: u8,
+    },
 }
 
 impl<T> RandomSequenceBuilder<T>
 where
     T: QuadraticResidue
 {
-    /// Initialise a config from stored settings. Not recommended unless you know what you're doing,
-    /// or these values have been taken from an already serialized RandomSequenceBuilder.
+    /// Initialise a config from stored settings, checking the `prime` with
+    /// [RandomSequenceBuilder::validate]. Not recommended unless you know what you're doing, or these
+    /// values have been taken from an already serialized RandomSequenceBuilder.
     ///
     /// Prefer [RandomSequenceBuilderInit::new] instead.
+    ///
+    /// # Errors
+    ///
+    /// If the `prime` isn't a prime number satisfying `prime = 3 mod 4`.
+    pub fn try_from_spec(
+        seed_base: T,
+        seed_offset: T,
+        init_base: T,
+        init_offset: T,
+        prime: T,
+        intermediate_xor: T,
+    ) -> Result<Self, Error> {
+        // SAFETY: the prime is validated before the config is returned
+        let config = unsafe { Self::from_spec(seed_base, seed_offset, init_base, init_offset, prime, intermediate_xor) };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Initialise a config from stored settings, without checking them. Not recommended unless you
+    /// know what you're doing, or these values have been taken from an already serialized
+    /// RandomSequenceBuilder.
+    ///
+    /// Prefer [RandomSequenceBuilderInit::new] instead, or [RandomSequenceBuilder::try_from_spec] to
+    /// validate the settings.
+    ///
+    /// # Safety
+    ///
+    /// The `prime` must be a prime number satisfying `prime = 3 mod 4`, otherwise the sequence
+    /// is no longer guaranteed to produce unique values.
     pub unsafe fn from_spec(
         seed_base: T,
         seed_offset: T,
@@ -67,196 +240,1202 @@ where
             init_offset,
             prime,
             intermediate_xor,
+            domain: None,
+            range_start: T::zero(),
+            range_step: T::one(),
+            backend: Backend::QuadraticResidue,
+            rounds: DEFAULT_ROUNDS,
+            key_schedule: KeySchedule::Legacy,
+            fold_tail: false,
         }
     }
 
-    /// Intermediary function to compute the quadratic prime residue.
-    #[inline]
-    pub(crate) fn permute_qpr(&self, x: T) -> T {
-        // The small set of integers out of range are mapped to themselves.
-        if x >= self.prime {
-            return x;
+    /// Check the parameters define a sequence of unique values.
+    ///
+    /// The builder methods always produce valid parameters, so this is only needed for configs from
+    /// [RandomSequenceBuilder::from_spec] or with modified fields. Deserialization runs the same
+    /// checks. The `prime` is checked with a deterministic Miller-Rabin test, which is proven exact
+    /// for every prime up to 64 bits. Wider primes are checked with the Baillie-PSW test, which
+    /// has no known counterexample but is only a probable prime test.
+    ///
+    /// # Errors
+    ///
+    /// The first [Error] found in the parameters.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.domain == Some(T::zero()) {
+            return Err(Error::EmptyDomain);
+        }
+        // the range must end within the type in either its unsigned order, or the two's complement
+        // order of a signed range, whose negative start is stored as its bit pattern
+        let sign = T::one() << (type_bits::<T>() as usize - 1);
+        let fits = self.max_index().checked_mul(&self.range_step).is_some_and(|span| {
+            span.checked_add(&self.range_start).is_some() || span.checked_add(&(self.range_start ^ sign)).is_some()
+        });
+        if self.range_step == T::zero() || !fits {
+            return Err(Error::InvalidRange);
+        }
+        if self.rounds == 0 || matches!(self.backend, Backend::Feistel { rounds: 0 }) {
+            return Err(Error::ZeroRounds);
+        }
+        if type_bits::<T>() > 128 && self.backend != Backend::QuadraticResidue {
+            return Err(Error::BackendUnsupported);
         }
 
-        // (x * x) % prime; but done safely to avoid integer overflow on x * x
-        // let xm = MontgomeryInt::new(x, &self.prime);
-        // let residue = (xm * xm).residue();
+        // a smaller prime leaves more values mapped to themselves, but is still a permutation
+        if self.prime > self.mask() {
+            return Err(Error::PrimeOutOfRange);
+        }
+        let three = T::one() + T::one() + T::one();
+        if self.prime & three != three {
+            return Err(Error::NotThreeModFour);
+        }
+        if !is_prime(self.prime) {
+            return Err(Error::NotPrime);
+        }
+        if self.tail_window().is_some_and(|(start, _)| start > self.prime) {
+            return Err(Error::PrimeTooSmall);
+        }
+        Ok(())
+    }
 
-        let residue = x.residue(self.prime);
+    /// Restrict the sequence to the values `0..domain`, rather than the entire range of `T`.
+    ///
+    /// The permutation runs over the smallest power of two `2^k >= domain`, using the largest prime
+    /// `prime < 2^k` satisfying `prime = 3 mod 4`. Outputs that fall outside of the domain are
+    /// cycle-walked back into it by re-applying the permutation until they land in `0..domain`.
+    ///
+    /// As `2^k < 2 * domain`, computing a value takes fewer than two permutations on average. The
+    /// worst case is `2^k - domain + 1` permutations, as cycle-walking can only visit each value
+    /// outside of the domain once.
+    ///
+    /// # Panics
+    ///
+    /// If `domain` is zero.
+    pub fn with_domain(mut self, domain: T) -> Self {
+        assert!(domain > T::zero(), "The domain must contain at least one value.");
+        self.domain = Some(domain);
+        self.prime = prime_below(domain_bits(domain));
+        self
+    }
 
-        // Op: `self.prime / 2` the bit shift is used to get around rust types
-        if x <= self.prime >> 1 {
-            residue
-        } else {
-            self.prime - residue
-        }
+    /// Restrict the sequence to the `bits`-bit values `0..2^bits`, such as 24-bit colours.
+    ///
+    /// Equivalent to [RandomSequenceBuilder::with_domain] with a domain of `2^bits`, which also
+    /// covers the full width of `T`. As the domain is a power of two, no values are cycle-walked.
+    /// See [RandomBitsSequence](crate::RandomBitsSequence) for sequences with a fixed bit width.
+    ///
+    /// # Panics
+    ///
+    /// If `bits` is zero or wider than `T`.
+    pub fn with_bits(self, bits: u32) -> Self {
+        assert!(bits > 0 && bits <= type_bits::<T>(), "The bit width must be from 1 to {} bits.", type_bits::<T>());
+        self.with_range_span(T::zero(), T::max_value() >> (type_bits::<T>() - bits) as usize, T::one())
     }
-}
 
-impl<T> IntoIterator for RandomSequenceBuilder<T>
-where
-    T: QuadraticResidue,
-    RandomSequence<T>: Iterator<Item = T>,
-{
-    type Item = T;
-    type IntoIter = RandomSequence<T>;
+    /// Generate the sequence with a different permutation [Backend].
+    ///
+    /// # Panics
+    ///
+    /// If the [Backend::Feistel] backend has zero rounds, which would generate the identity, or
+    /// `T` is wider than 128 bits and the backend isn't [Backend::QuadraticResidue].
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        assert!(!matches!(backend, Backend::Feistel { rounds: 0 }), "The permutation requires at least one round.");
+        assert!(
+            type_bits::<T>() <= 128 || backend == Backend::QuadraticResidue,
+            "The backend only supports types up to 128 bits."
+        );
+        self.backend = backend;
+        self
+    }
 
-    /// Build a [RandomSequence] iterator from this config.
-    fn into_iter(self) -> Self::IntoIter {
-        let start_index = self.permute_qpr(self.permute_qpr(self.seed_base).wrapping_add(&self.init_base));
-        let intermediate_offset = self.permute_qpr(self.permute_qpr(self.seed_offset).wrapping_add(&self.init_offset));
+    /// Set the number of residue passes of the [Backend::QuadraticResidue] permutation, trading
+    /// speed for a better mixed output. Each pass costs one modular multiplication to compute a
+    /// value, and one modular exponentiation to invert it.
+    ///
+    /// The default of 2 rounds computes `qpr(qpr(index) + intermediate_offset ^ intermediate_xor)`.
+    /// Each additional round applies `qpr(x + offset ^ xor)` again, with a new offset and xor.
+    ///
+    /// # Panics
+    ///
+    /// If `rounds` is zero.
+    pub fn with_rounds(mut self, rounds: u8) -> Self {
+        assert!(rounds > 0, "The permutation requires at least one round.");
+        self.rounds = rounds;
+        self
+    }
 
-        RandomSequence {
-            config: self,
-            start_index,
-            current_index: T::zero(),
-            intermediate_offset,
-            ended: false,
+    /// Derive the round keys from the seeds with a different [KeySchedule].
+    ///
+    /// Builders default to [KeySchedule::Legacy], which generates the same sequence from the same
+    /// seeds as earlier versions of the crate. [KeySchedule::Seeded] changes the sequence of every
+    /// seed pair, so only opt in for sequences which don't need to match stored output.
+    pub fn with_key_schedule(mut self, key_schedule: KeySchedule) -> Self {
+        self.key_schedule = key_schedule;
+        self
+    }
+
+    /// Enable or disable scrambling the tail of values `>= prime` in each residue pass.
+    ///
+    /// The quadratic residue only permutes the values below the prime, and maps the few values
+    /// from the prime up to the top of the range, such as 251 to 255 for u8, to themselves. With
+    /// `fold_tail` enabled, each pass also applies a residue of a small prime over a window holding
+    /// the tail and the values just below it, so no value passes through unchanged. The window is
+    /// only hit by a small fraction of values, so costs almost nothing on average.
+    ///
+    /// The window is sized from the tail, so also holds the larger tail of a smaller `prime` from
+    /// [RandomSequenceBuilder::try_from_spec]. A `prime` too small for any window of the range to
+    /// hold its tail fails [RandomSequenceBuilder::validate] with [Error::PrimeTooSmall].
+    ///
+    /// Disabled by default, as enabling it changes the sequence generated by the same seeds.
+    pub fn with_fold_tail(mut self, fold_tail: bool) -> Self {
+        self.fold_tail = fold_tail;
+        self
+    }
+
+    /// Map the sequence onto the values in `range`, such as `49152..=65535`.
+    ///
+    /// Equivalent to [RandomSequenceBuilder::with_range_step] with a step of 1.
+    ///
+    /// # Panics
+    ///
+    /// If `range` is empty.
+    pub fn with_range(self, range: RangeInclusive<T>) -> Self {
+        self.with_range_step(range, T::one())
+    }
+
+    /// Map the sequence onto every `step`-th value in `range`, starting from `range.start()`, the
+    /// same values as `range.step_by(step)`.
+    ///
+    /// The sequence index runs over `0..len` for the `len` values in the range, and each
+    /// output is computed as `range.start() + step * x` for an `x` generated over the domain
+    /// `0..len` as with [RandomSequenceBuilder::with_domain].
+    ///
+    /// # Panics
+    ///
+    /// If `range` is empty, or `step` is zero.
+    pub fn with_range_step(self, range: RangeInclusive<T>, step: T) -> Self {
+        let (start, end) = range.into_inner();
+        assert!(start <= end, "The range must contain at least one value.");
+        self.with_range_span(start, end - start, step)
+    }
+
+    /// Map the sequence onto every `step`-th value from `range_start` to `range_start + span`.
+    pub(crate) fn with_range_span(mut self, range_start: T, span: T, step: T) -> Self {
+        assert!(step > T::zero(), "The step must be greater than zero.");
+        self.range_start = range_start;
+        self.range_step = step;
+        match (span / step).checked_add(&T::one()) {
+            Some(domain) => self.with_domain(domain),
+            None => {
+                // the range covers every value of T
+                self.domain = None;
+                self.prime = prime_below(type_bits::<T>());
+                self
+            }
         }
     }
-}
 
-impl RandomSequenceBuilder<u8> {
-    /// Initialise a [RandomSequenceBuilder] from a specific seed pair.
-    pub fn new(seed_base: u8, seed_offset: u8) -> Self {
-        Self {
-            seed_base,
-            seed_offset,
-            init_base: 167,
-            init_offset: 181,
-            prime: 251,
-            intermediate_xor: 137,
+    /// The largest index in the sequence, `domain - 1`.
+    #[inline]
+    pub(crate) fn max_index(&self) -> T {
+        match self.domain {
+            Some(domain) => domain - T::one(),
+            None => T::max_value(),
         }
     }
-}
 
-impl RandomSequenceBuilder<u16> {
-    /// Initialise a [RandomSequenceBuilder] from a specific seed pair.
-    pub fn new(seed_base: u16, seed_offset: u16) -> Self {
-        Self {
-            seed_base,
-            seed_offset,
-            init_base: 0x682f,
-            init_offset: 0x4679,
-            prime: 65519,
-            intermediate_xor: 0x5bf0,
+    /// Check whether a value is within the domain of the permutation.
+    #[inline]
+    pub(crate) fn in_domain(&self, value: T) -> bool {
+        self.domain.map_or(true, |domain| value < domain)
+    }
+
+    /// Map a permuted value in the domain onto the sequence range.
+    #[inline(always)]
+    pub(crate) fn map_range(&self, value: T) -> T {
+        self.range_start.wrapping_add(&(value * self.range_step))
+    }
+
+    /// Map a value in the sequence range back onto the domain, or `None` if the value isn't in the
+    /// sequence.
+    #[inline]
+    pub(crate) fn unmap_range(&self, value: T) -> Option<T> {
+        let offset = value.wrapping_sub(&self.range_start);
+        if offset % self.range_step != T::zero() {
+            return None;
         }
+        let value = offset / self.range_step;
+        self.in_domain(value).then_some(value)
     }
-}
 
-impl RandomSequenceBuilder<u32> {
-    /// Initialise a [RandomSequenceBuilder] from a specific seed pair.
-    pub fn new(seed_base: u32, seed_offset: u32) -> Self {
-        Self {
-            seed_base,
-            seed_offset,
-            init_base: 0x682f0161,
-            init_offset: 0x46790905,
-            prime: 4294967291,
-            intermediate_xor: 0x5bf03635,
+    /// The bit mask of the power of two range `0..2^k` that the permutation operates over.
+    #[inline]
+    pub(crate) fn mask(&self) -> T {
+        match self.domain {
+            Some(domain) => T::max_value() >> (type_bits::<T>() - domain_bits(domain)) as usize,
+            None => T::max_value(),
         }
     }
-}
 
-impl RandomSequenceBuilder<u64> {
-    /// Initialise a [RandomSequenceBuilder] from a specific seed pair.
-    pub fn new(seed_base: u64, seed_offset: u64) -> Self {
-        Self {
-            seed_base,
-            seed_offset,
-            init_base: 0x682f01615bf03635,
-            init_offset: 0x46790905682f0161,
-            prime: 18446744073709551427,
-            intermediate_xor: 0x5bf0363546790905,
+    /// Compute `(index + offset) mod domain`, where `index` is already within the domain.
+    #[inline]
+    pub(crate) fn add_index(&self, index: T, offset: T) -> T {
+        match self.domain {
+            Some(domain) => {
+                let offset = offset % domain;
+                if index >= domain - offset { index - (domain - offset) } else { index + offset }
+            }
+            None => index.wrapping_add(&offset),
         }
     }
-}
 
-impl RandomSequenceBuilder<usize> {
-    /// Initialise a [RandomSequenceBuilder] from a specific seed pair.
-    pub fn new(seed_base: usize, seed_offset: usize) -> Self {
-        Self {
-            seed_base,
-            seed_offset,
-            init_base: 0x682f01615bf03635,
-            init_offset: 0x46790905682f0161,
-            prime: 18446744073709551427,
-            intermediate_xor: 0x5bf0363546790905,
+    /// Compute `(index - offset) mod domain`, where both values are already within the domain.
+    #[inline]
+    pub(crate) fn sub_index(&self, index: T, offset: T) -> T {
+        match self.domain {
+            Some(domain) if index < offset => index + (domain - offset),
+            _ => index.wrapping_sub(&offset),
         }
     }
-}
 
-pub trait QuadraticResidue
-where
-    Self: PrimInt + AsPrimitive<usize> + WrappingAdd + WrappingSub
-{
-    /// Compute the quadratic residue of this number against a prime.
-    fn residue(self, prime: Self) -> Self;
-}
+    /// Intermediary function to compute the quadratic prime residue.
+    #[inline]
+    pub(crate) fn permute_qpr(&self, x: T) -> T {
+        x.permute_qpr(self.prime)
+    }
 
-macro_rules! impl_residue {
-    ($base_type:ident, $larger_type:ident) => {
-        impl QuadraticResidue for $base_type {
-            fn residue(self, prime: Self) -> Self {
-                ((self as $larger_type * self as $larger_type) % prime as $larger_type) as Self
-            }
-        }
-    };
-}
+    /// Inverse of [RandomSequenceBuilder::permute_qpr], such that `invert_qpr(permute_qpr(x)) == x`.
+    #[inline]
+    pub(crate) fn invert_qpr(&self, y: T) -> T {
+        invert_qpr(y, self.prime)
+    }
 
-impl_residue!(u8, u16);
-impl_residue!(u16, u32);
-impl_residue!(u32, u64);
-impl_residue!(u64, u128);
-#[cfg(target_pointer_width = "64")]
-impl_residue!(usize, u128);
-#[cfg(target_pointer_width = "32")]
-impl_residue!(usize, u64);
-#[cfg(not(any(target_pointer_width = "64", target_pointer_width = "32")))]
-compile_error!("Unsupported pointer width.");
+    /// The `(start, tail_prime)` of the window of `tail_prime` values at the top of the permutation
+    /// range, which scrambles the tail of values `>= prime` that [RandomSequenceBuilder::permute_qpr]
+    /// maps to themselves. `None` if [RandomSequenceBuilder::fold_tail] is disabled.
+    ///
+    /// The `tail_prime` is the largest prime with 2 more bits than the tail, so the window holds the
+    /// tail and at least as many values below it. A tail within 2 bits of the range uses the
+    /// `prime` itself when it's more than half of the range, or otherwise the largest prime of the
+    /// range, so the window still holds the larger tail of a smaller `prime`.
+    #[inline]
+    pub(crate) fn tail_window(&self) -> Option<(T, T)> {
+        T::tail_window(self)
+    }
 
-#[cfg(test)]
-mod tests {
-    use std::collections::hash_map::Entry;
-    use std::collections::HashMap;
-    use std::string::ToString;
+    /// A single residue pass, `permute_qpr(x)` followed by the residue over the tail window.
+    #[inline(always)]
+    pub(crate) fn permute_pass(&self, x: T, tail_window: Option<(T, T)>) -> T {
+        permute_tail(self.permute_qpr(x), tail_window)
+    }
 
-    use super::*;
+    /// [RandomSequenceBuilder::permute_pass] of `x < prime` during a sequential walk, where `last`
+    /// holds the previous `x` of the walk and its square `x^2 mod prime`.
+    ///
+    /// When `x` is a neighbour of the previous `x`, its square is updated with two modular
+    /// additions, as `(x + 1)^2 = x^2 + x + (x + 1)` and `(x - 1)^2 = x^2 - x - (x - 1)`, rather
+    /// than multiplied and reduced again. Returns the pass and the square of `x`.
+    #[inline(always)]
+    pub(crate) fn permute_pass_step(&self, x: T, last: Option<(T, T)>, tail_window: Option<(T, T)>) -> (T, T) {
+        let prime = self.prime;
+        let square = match last {
+            Some((last, square)) if last < x && x - last == T::one() => add_mod(add_mod(square, last, prime), x, prime),
+            Some((last, square)) if x < last && last - x == T::one() => sub_mod(sub_mod(square, last, prime), x, prime),
+            _ => x.residue(prime),
+        };
+        let y = if x <= prime >> 1 { square } else { prime - square };
+        (permute_tail(y, tail_window), square)
+    }
 
-    /// Check the prime satisfies `p = 3 mod 4`.
-    fn is_3_mod_4(n: u64) -> bool {
-        n % 4 == 3
+    /// Inverse of [RandomSequenceBuilder::permute_pass].
+    #[inline(always)]
+    pub(crate) fn invert_pass(&self, y: T, tail_window: Option<(T, T)>) -> T {
+        let y = match tail_window {
+            Some((start, tail_prime)) if y >= start => rotate_window(y, start, tail_prime, invert_qpr),
+            _ => y,
+        };
+        self.invert_qpr(y)
     }
 
-    fn is_send<T: Send>() {}
-    fn is_sync<T: Sync>() {}
+    /// Derive the [Keys] of the permutation from the seeds.
+    ///
+    /// The `start_index` is derived from `seed_base`, and the offset and xor of the first round
+    /// after the initial residue from `seed_offset` and the fixed `intermediate_xor` with the
+    /// [KeySchedule::Legacy] schedule, or from both seeds with [KeySchedule::Seeded].
+    #[inline]
+    pub(crate) fn keys(&self) -> Keys<T> {
+        let start_index = match self.domain {
+            Some(domain) => self.derive_key(self.seed_base, self.init_base) % domain,
+            None => self.derive_key(self.seed_base, self.init_base),
+        };
 
-    macro_rules! test_config {
-        ($name:ident, $type:ident, $check:literal) => {
-            #[test]
-            fn $name() {
-                let config = RandomSequenceBuilder::<$type>::new(0, 0);
-                let config_orig = config.clone();
+        let (intermediate_offset, intermediate_xor) = match self.key_schedule {
+            KeySchedule::Legacy => (self.derive_key(self.seed_offset, self.init_offset), self.intermediate_xor),
+            KeySchedule::Seeded => {
+                let base = self.derive_key(self.seed_base, self.init_base);
+                let offset = self.derive_key(self.seed_offset, self.init_offset);
+                (
+                    self.derive_key(self.seed_offset ^ base, self.init_offset),
+                    self.derive_key(self.seed_base ^ offset, self.intermediate_xor),
+                )
+            }
+        };
 
-                // check the configured prime number satisfies the requirements
-                // is_prime crate makes this very quick for u64
-                let is_prime_res = is_prime::is_prime(&config.prime.to_string());
-                let is_3_mod_4_res = is_3_mod_4(config.prime as u64);
+        Keys {
+            start_index,
+            intermediate_offset,
+            intermediate_xor,
+            tail_window: self.tail_window(),
+            feistel_key: self.feistel_key(),
+        }
+    }
 
-                let mut found_number = config.prime;
-                if !is_prime_res || !is_3_mod_4_res {
-                    if found_number % 2 == 0 {
-                        found_number -= 1;
+    /// Pseudo-randomise a seed within the permutation range, `qpr(qpr(seed) + init)`.
+    #[inline]
+    fn derive_key(&self, seed: T, init: T) -> T {
+        let mask = self.mask();
+        self.permute_qpr(self.permute_qpr(seed & mask).wrapping_add(&init) & mask)
+    }
+
+    /// Compute the sequence value for an absolute index, cycle-walking values outside of the domain.
+    ///
+    /// `qpr(qpr(index) + intermediate_offset ^ intermediate_xor)` for the default 2 rounds, with
+    /// each further round applying `qpr(x + offset ^ xor)` with the next round key.
+    #[inline(always)]
+    pub(crate) fn permute(&self, index: T, keys: &Keys<T>) -> T {
+        match self.backend {
+            Backend::Feistel { rounds } => {
+                let bits = self.mask().count_ones();
+                let mut value = index;
+                loop {
+                    value = from_u128(feistel::permute(to_u128(value), bits, keys.feistel_key, rounds));
+                    if self.in_domain(value) {
+                        return value;
                     }
+                }
+            }
+            _ => self.permute_rounds(self.permute_pass(index, keys.tail_window), keys),
+        }
+    }
 
-                    // suggest a suitable prime number (slow, but only run when there's a bad prime)
-                    let mut found_next_prime = false;
-                    let mut found_next_3_mod_4 = false;
+    /// Complete [RandomSequenceBuilder::permute] with the [Backend::QuadraticResidue] backend from
+    /// the first residue pass of the index, applying the rounds after the first and cycle-walking
+    /// values outside of the domain.
+    #[inline(always)]
+    pub(crate) fn permute_rounds(&self, first: T, keys: &Keys<T>) -> T {
+        let mask = self.mask();
+        let mut value = first;
+        loop {
+            let (mut offset, mut xor) = (keys.intermediate_offset, keys.intermediate_xor);
+            for _ in 1..self.rounds {
+                value = self.permute_pass((value.wrapping_add(&offset) & mask) ^ (xor & mask), keys.tail_window);
+                (offset, xor) = self.next_round_key(offset, xor, keys.intermediate_offset);
+            }
+            if self.in_domain(value) {
+                return value;
+            }
+            value = self.permute_pass(value, keys.tail_window);
+        }
+    }
 
-                    while !found_next_prime || !found_next_3_mod_4 {
-                        found_number -= 2;
-                        found_next_prime = is_prime::is_prime(&found_number.to_string());
-                        found_next_3_mod_4 = is_3_mod_4(found_number as u64);
+    /// Inverse of [RandomSequenceBuilder::permute], recovering the absolute index of a value.
+    ///
+    /// `qpr^-1(qpr^-1(value) ^ intermediate_xor - intermediate_offset)` for the default 2 rounds,
+    /// unwinding any further rounds first.
+    #[inline(always)]
+    pub(crate) fn invert(&self, value: T, keys: &Keys<T>) -> T {
+        let mask = self.mask();
+        let mut index = value;
+        match self.backend {
+            Backend::QuadraticResidue => {
+                // the round keys of the last round, to be unwound in reverse
+                let (mut last_offset, mut last_xor) = (keys.intermediate_offset, keys.intermediate_xor);
+                for _ in 2..self.rounds {
+                    (last_offset, last_xor) = self.next_round_key(last_offset, last_xor, keys.intermediate_offset);
+                }
+                loop {
+                    let (mut offset, mut xor) = (last_offset, last_xor);
+                    for round in (1..self.rounds).rev() {
+                        index = (self.invert_pass(index, keys.tail_window) ^ (xor & mask)).wrapping_sub(&offset) & mask;
+                        if round > 1 {
+                            (offset, xor) = self.prev_round_key(offset, xor, keys.intermediate_offset);
+                        }
+                    }
+                    index = self.invert_pass(index, keys.tail_window);
+                    if self.in_domain(index) {
+                        return index;
+                    }
+                }
+            }
+            Backend::Feistel { rounds } => {
+                let bits = mask.count_ones();
+                loop {
+                    index = from_u128(feistel::invert(to_u128(index), bits, keys.feistel_key, rounds));
+                    if self.in_domain(index) {
+                        return index;
+                    }
+                }
+            }
+        }
+    }
+
+    /// The `(offset, xor)` of the round after the given round, where the first round after the
+    /// initial residue uses `(intermediate_offset, intermediate_xor)`.
+    ///
+    /// `offset_r = r * intermediate_offset` and `xor_r = xor_(r-1) ^ offset_r`
+    #[inline(always)]
+    fn next_round_key(&self, offset: T, xor: T, intermediate_offset: T) -> (T, T) {
+        T::next_round_key(offset, xor, intermediate_offset)
+    }
+
+    /// Inverse of [RandomSequenceBuilder::next_round_key].
+    #[inline(always)]
+    fn prev_round_key(&self, offset: T, xor: T, intermediate_offset: T) -> (T, T) {
+        (offset.wrapping_sub(&intermediate_offset), xor ^ offset)
+    }
+
+    /// The key for the [Backend::Feistel] round function, derived from both seeds, or zero for the
+    /// other backends, which don't widen the seeds to a u128.
+    #[inline]
+    fn feistel_key(&self) -> u64 {
+        match self.backend {
+            Backend::Feistel { .. } => {
+                feistel::key(to_u128(self.seed_base ^ self.init_base), to_u128(self.seed_offset ^ self.init_offset))
+            }
+            _ => 0,
+        }
+    }
+
+    /// Get the index in the sequence of a given value, the inverse of [RandomSequence::n].
+    ///
+    /// Equivalent to `self.into_iter().index_of(value)`.
+    ///
+    /// # Panics
+    ///
+    /// If `value` isn't part of the sequence.
+    #[inline]
+    pub fn index_of(&self, value: T) -> T {
+        self.index_of_internal(&self.keys(), value)
+            .expect("The value isn't part of the sequence.")
+    }
+
+    /// Invert the sequence, and then the `start_index` offset, to recover the index of a value, or
+    /// `None` if the value isn't part of the sequence.
+    ///
+    /// `qpr^-1(qpr^-1(value) ^ intermediate_xor - intermediate_offset) - start_index`
+    #[inline(always)]
+    pub(crate) fn index_of_internal(&self, keys: &Keys<T>, value: T) -> Option<T> {
+        let value = self.unmap_range(value)?;
+        let actual_index = self.invert(value, keys);
+        Some(self.sub_index(actual_index, keys.start_index))
+    }
+}
+
+/// The values a [RandomSequenceBuilder] derives from its seeds and parameters, computed once per
+/// sequence rather than for every value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Keys<T> {
+    /// The absolute index the sequence starts from.
+    pub(crate) start_index: T,
+
+    /// The offset of the first round after the initial residue.
+    pub(crate) intermediate_offset: T,
+
+    /// The xor of the first round after the initial residue.
+    pub(crate) intermediate_xor: T,
+
+    /// The `(start, tail_prime)` of the tail window, see [RandomSequenceBuilder::tail_window].
+    pub(crate) tail_window: Option<(T, T)>,
+
+    /// The key of the [Backend::Feistel] round function, see [RandomSequenceBuilder::feistel_key].
+    pub(crate) feistel_key: u64,
+}
+
+impl<T> IntoIterator for RandomSequenceBuilder<T>
+where
+    T: QuadraticResidue + SequenceInt<Unsigned = T>,
+    RandomSequence<T>: Iterator<Item = T>,
+{
+    type Item = T;
+    type IntoIter = RandomSequence<T>;
+
+    /// Build a [RandomSequence] iterator from this config.
+    fn into_iter(self) -> Self::IntoIter {
+        RandomSequence::from(self)
+    }
+}
+
+/// Initialise a [RandomSequenceBuilder] with the default parameters of its type.
+///
+/// Implemented for each of `u8`, `u16`, `u32`, `u64`, `usize` and `u128`, so generic code can
+/// construct builders and sequences over any of them. The builders also have inherent `new` and
+/// `rand` methods, which don't require the trait to be imported.
+///
+/// ```rust
+/// use rand_unique::{RandomSequence, RandomSequenceBuilder, RandomSequenceBuilderInit, SequenceInt};
+///
+/// fn first<T>(seed_base: T::Unsigned, seed_offset: T::Unsigned) -> Option<T>
+/// where
+///     T: SequenceInt,
+///     RandomSequenceBuilder<T::Unsigned>: RandomSequenceBuilderInit<T::Unsigned>,
+/// {
+///     let config = RandomSequenceBuilder::<T::Unsigned>::new(seed_base, seed_offset);
+///     RandomSequence::<T>::from(config).next()
+/// }
+///
+/// assert_eq!(first::<u32>(7, 11), RandomSequence::<u32>::new(7, 11).next());
+/// assert_eq!(first::<i8>(7, 11), RandomSequence::<i8>::new(7, 11).next());
+/// ```
+pub trait RandomSequenceBuilderInit<T>: Sized
+where
+    T: QuadraticResidue
+{
+    /// The default [RandomSequenceBuilder::init_base].
+    const INIT_BASE: T;
+
+    /// The default [RandomSequenceBuilder::init_offset].
+    const INIT_OFFSET: T;
+
+    /// The default [RandomSequenceBuilder::prime], the largest prime in `T` satisfying
+    /// `prime = 3 mod 4`.
+    const PRIME: T;
+
+    /// The default `intermediate_xor` of a [RandomSequenceBuilder].
+    const INTERMEDIATE_XOR: T;
+
+    /// Initialise a [RandomSequenceBuilder] from a specific seed pair.
+    fn new(seed_base: T, seed_offset: T) -> Self;
+
+    /// Initialise a [RandomSequenceBuilder] from a random seed.
+    #[cfg(feature = "rand")]
+    fn rand(rng: &mut impl rand::RngCore) -> Self {
+        let seed_base = crate::rand::next_seed(rng);
+        let seed_offset = crate::rand::next_seed(rng);
+        Self::new(seed_base, seed_offset)
+    }
+}
+
+macro_rules! init_builder {
+    ($type:ident, $same_width:ident) => {
+        init_builder!(
+            $type,
+            <RandomSequenceBuilder<$same_width> as RandomSequenceBuilderInit<$same_width>>::INIT_BASE as $type,
+            <RandomSequenceBuilder<$same_width> as RandomSequenceBuilderInit<$same_width>>::INIT_OFFSET as $type,
+            <RandomSequenceBuilder<$same_width> as RandomSequenceBuilderInit<$same_width>>::PRIME as $type,
+            <RandomSequenceBuilder<$same_width> as RandomSequenceBuilderInit<$same_width>>::INTERMEDIATE_XOR as $type
+        );
+    };
+    ($type:ident, $init_base:expr, $init_offset:expr, $prime:expr, $intermediate_xor:expr) => {
+        impl RandomSequenceBuilderInit<$type> for RandomSequenceBuilder<$type> {
+            const INIT_BASE: $type = $init_base;
+            const INIT_OFFSET: $type = $init_offset;
+            const PRIME: $type = $prime;
+            const INTERMEDIATE_XOR: $type = $intermediate_xor;
+
+            fn new(seed_base: $type, seed_offset: $type) -> Self {
+                RandomSequenceBuilder::<$type>::new(seed_base, seed_offset)
+            }
+        }
+
+        impl RandomSequenceBuilder<$type> {
+            /// Initialise a [RandomSequenceBuilder] from a specific seed pair.
+            ///
+            /// A `const fn`, see [RandomSequence::const_new] to also evaluate the sequence at compile
+            /// time.
+            pub const fn new(seed_base: $type, seed_offset: $type) -> Self {
+                Self {
+                    seed_base,
+                    seed_offset,
+                    init_base: Self::INIT_BASE,
+                    init_offset: Self::INIT_OFFSET,
+                    prime: Self::PRIME,
+                    intermediate_xor: Self::INTERMEDIATE_XOR,
+                    domain: None,
+                    range_start: 0,
+                    range_step: 1,
+                    backend: Backend::QuadraticResidue,
+                    rounds: DEFAULT_ROUNDS,
+                    key_schedule: KeySchedule::Legacy,
+                    fold_tail: false,
+                }
+            }
+        }
+    };
+}
+
+init_builder!(u8, 167, 181, 251, 137);
+init_builder!(u16, 0x682f, 0x4679, 65519, 0x5bf0);
+init_builder!(u32, 0x682f0161, 0x46790905, 4294967291, 0x5bf03635);
+init_builder!(u64, 0x682f01615bf03635, 0x46790905682f0161, 18446744073709551427, 0x5bf0363546790905);
+// usize shares the parameters of the fixed width type of the target, see PortableSequence to
+// generate the same sequence on every target
+#[cfg(target_pointer_width = "64")]
+init_builder!(usize, u64);
+#[cfg(target_pointer_width = "32")]
+init_builder!(usize, u32);
+init_builder!(
+    u128,
+    0x682f01615bf0363546790905682f0161,
+    0x46790905682f01615bf0363546790905,
+    340282366920938463463374607431768211283,
+    0x5bf0363546790905682f01615bf03635
+);
+
+/// Compute `(a + b) mod prime` for `a` and `b` below the prime, without overflowing.
+#[inline(always)]
+fn add_mod<T: QuadraticResidue>(a: T, b: T, prime: T) -> T {
+    if a >= prime - b { a - (prime - b) } else { a + b }
+}
+
+/// Compute `(a - b) mod prime` for `a` and `b` below the prime.
+#[inline(always)]
+fn sub_mod<T: QuadraticResidue>(a: T, b: T, prime: T) -> T {
+    if a < b { a + (prime - b) } else { a - b }
+}
+
+/// Inverse of [QuadraticResidue::permute_qpr], such that `invert_qpr(x.permute_qpr(prime), prime) == x`.
+///
+/// As `prime = 3 mod 4`, exactly one of `y` and `prime - y` is a quadratic residue, and its
+/// square roots are `±r` where `r = y^((prime + 1) / 4) mod prime`. The root `<= prime / 2`
+/// was squared directly, the root `> prime / 2` produced `prime - residue`.
+#[inline]
+fn invert_qpr<T: QuadraticResidue>(y: T, prime: T) -> T {
+    // The small set of integers out of range are mapped to themselves.
+    if y >= prime {
+        return y;
+    }
+
+    let half = prime >> 1;
+    let root = sqrt_mod_prime(y, prime);
+    if root.residue(prime) == y {
+        if root <= half { root } else { prime - root }
+    } else {
+        let root = sqrt_mod_prime(prime - y, prime);
+        if root > half { root } else { prime - root }
+    }
+}
+
+/// Candidate square root of `y`, computed as `y^((prime + 1) / 4) mod prime`.
+///
+/// Only a true square root if `y` is a quadratic residue of `prime`.
+#[inline]
+fn sqrt_mod_prime<T: QuadraticResidue>(y: T, prime: T) -> T {
+    // Op: `(prime + 1) / 4` without overflowing, valid as `prime = 3 mod 4`
+    let mut exponent = (prime >> 2) + T::one();
+    let mut base = y;
+    let mut result = T::one();
+    while exponent > T::zero() {
+        if exponent & T::one() == T::one() {
+            result = result.mul_mod(base, prime);
+        }
+        base = base.residue(prime);
+        exponent = exponent >> 1;
+    }
+    result
+}
+
+/// Apply the residue `f` to `y` over the window `start..start + prime`, rotated down by one.
+///
+/// The residue fixes `0`, `1` and `prime - 1`, which the rotation moves to `start + 1`,
+/// `start + 2` and `start`, below the tail at the top of the window.
+#[inline]
+fn rotate_window<T: QuadraticResidue>(y: T, start: T, prime: T, f: fn(T, T) -> T) -> T {
+    let last = prime - T::one();
+    let local = f(if y == start { last } else { y - start - T::one() }, prime);
+    if local == last { start } else { start + local + T::one() }
+}
+
+/// The residue over the tail window of a pass, applied to `y = permute_qpr(x)`.
+#[inline(always)]
+fn permute_tail<T: QuadraticResidue>(y: T, tail_window: Option<(T, T)>) -> T {
+    match tail_window {
+        Some((start, tail_prime)) if y >= start => rotate_window(y, start, tail_prime, T::permute_qpr),
+        _ => y,
+    }
+}
+
+/// Widen an integer to a u128.
+#[inline(always)]
+pub(crate) fn to_u128<T: PrimInt>(value: T) -> u128 {
+    value.to_u128().expect("Only types up to 128 bits can be widened to a u128.")
+}
+
+/// Narrow a u128 back to an integer type, for values known to fit.
+#[inline(always)]
+pub(crate) fn from_u128<T: QuadraticResidue>(value: u128) -> T {
+    T::from_u128(value).expect("Value must fit in the integer type.")
+}
+
+/// The number of bits in type `T`.
+#[inline(always)]
+pub(crate) fn type_bits<T: PrimInt>() -> u32 {
+    T::zero().count_zeros()
+}
+
+/// The number of bits `k` required for the power of two range `0..2^k` to cover `0..domain`,
+/// with a minimum of 2 bits for the smallest prime of 3.
+#[inline(always)]
+pub(crate) fn domain_bits<T: PrimInt>(domain: T) -> u32 {
+    (type_bits::<T>() - (domain - T::one()).leading_zeros()).max(2)
+}
+
+/// An unsigned integer type that a [RandomSequenceBuilder] can permute, by the modular arithmetic
+/// of the quadratic residue.
+///
+/// Implemented for `u8`, `u16`, `u32`, `u64`, `usize` and `u128`, and can be implemented for other
+/// fixed-width unsigned integers, such as a 256-bit integer, to generate sequences over them. An
+/// implementation must uphold:
+/// - The [PrimInt] operations behave as for the primitive unsigned integers, with
+///   `count_zeros()` of zero giving the bit width of the type.
+/// - [FromPrimitive::from_u64] and [FromPrimitive::from_u128] convert every value that fits in
+///   the type, which is used for the primes of the prime table and the seeds. Conversions through
+///   `NumCast` aren't used.
+/// - For any odd `prime` and any `self`, `other` below it, [QuadraticResidue::mul_mod] returns
+///   `(self * other) % prime` computed without overflow, and [QuadraticResidue::residue] returns
+///   `self.mul_mod(self, prime)`.
+///
+/// Violating any of these breaks the uniqueness of the sequence.
+///
+/// Builders of custom types are initialised with [RandomSequenceBuilder::try_from_spec], or by
+/// implementing [RandomSequenceBuilderInit]. Types wider than 128 bits are limited to the
+/// [Backend::QuadraticResidue] backend, as the other backends permute values as a u128, and
+/// [RandomSequenceBuilder::validate] rejects them with [Error::BackendUnsupported]. They search for
+/// the prime of a domain above 128 bits with a Baillie-PSW test, rather than the prime table.
+///
+/// [SequenceInt] must also be implemented to use a [RandomSequence] over the type. Its values are
+/// generated with [RandomSequence::next] and the other inherent methods, as `Iterator` is only
+/// implemented for the primitive integers.
+pub trait QuadraticResidue
+where
+    Self: PrimInt + FromPrimitive + WrappingAdd + WrappingSub
+{
+    /// Compute the quadratic residue of this number against a prime, `self^2 % prime`.
+    fn residue(self, prime: Self) -> Self;
+
+    /// Compute `(self * other) % prime` without overflowing.
+    fn mul_mod(self, other: Self, prime: Self) -> Self;
+
+    /// The quadratic prime residue, `self^2 mod prime` for `self <= prime / 2` and
+    /// `prime - self^2 mod prime` otherwise, a permutation of the values below the prime when
+    /// `prime = 3 mod 4`. Values `>= prime` map to themselves.
+    ///
+    /// Provided from [QuadraticResidue::residue], and the primitive integers share the `const fn`
+    /// of [RandomSequence::const_n].
+    #[inline]
+    fn permute_qpr(self, prime: Self) -> Self {
+        // The small set of integers out of range are mapped to themselves.
+        if self >= prime {
+            return self;
+        }
+
+        // (x * x) % prime; but done safely to avoid integer overflow on x * x
+        let residue = self.residue(prime);
+
+        // Op: `prime / 2` the bit shift is used to get around rust types
+        if self <= prime >> 1 {
+            residue
+        } else {
+            prime - residue
+        }
+    }
+
+    /// The tail window of a builder, see [RandomSequenceBuilder::fold_tail].
+    ///
+    /// Provided for any type, and the primitive integers share the `const fn` of
+    /// [RandomSequence::const_n].
+    #[inline]
+    fn tail_window(config: &RandomSequenceBuilder<Self>) -> Option<(Self, Self)> {
+        if !config.fold_tail {
+            return None;
+        }
+
+        let mask = config.mask();
+        let tail = mask - config.prime + Self::one();
+        let bits = type_bits::<Self>() - tail.leading_zeros();
+        let range_bits = mask.count_ones();
+        let tail_prime = if bits + 2 < range_bits {
+            prime_below::<Self>(bits + 2)
+        } else if config.prime > tail + Self::one() + Self::one() {
+            config.prime
+        } else {
+            prime_below::<Self>(range_bits)
+        };
+        Some((mask - tail_prime + Self::one(), tail_prime))
+    }
+
+    /// The `(offset, xor)` of the round after a round of the permutation, see
+    /// [RandomSequenceBuilder::rounds].
+    ///
+    /// Provided for any type, and the primitive integers share the `const fn` of
+    /// [RandomSequence::const_n].
+    #[inline(always)]
+    fn next_round_key(offset: Self, xor: Self, intermediate_offset: Self) -> (Self, Self) {
+        let offset = offset.wrapping_add(&intermediate_offset);
+        (offset, xor ^ offset)
+    }
+}
+
+/// The arithmetic of the permutation a primitive integer shares with the `const fn` of
+/// [RandomSequence::const_n], overriding the generic methods of [QuadraticResidue].
+macro_rules! const_arithmetic {
+    () => {
+        #[inline]
+        fn permute_qpr(self, prime: Self) -> Self {
+            Const::<Self>::permute_qpr(self, prime)
+        }
+
+        #[inline]
+        fn tail_window(config: &RandomSequenceBuilder<Self>) -> Option<(Self, Self)> {
+            Const::<Self>::tail_window(config)
+        }
+
+        #[inline(always)]
+        fn next_round_key(offset: Self, xor: Self, intermediate_offset: Self) -> (Self, Self) {
+            Const::<Self>::next_round_key(offset, xor, intermediate_offset)
+        }
+    };
+}
+
+/// The `(k, 2^k - 1, c)` parameters to fold values modulo `prime = 2^k - c`, where `2^k` is the
+/// smallest power of two above the prime, so `2^k = c mod prime`.
+macro_rules! fold_params {
+    ($type:ident, $prime:expr) => {{
+        let k = $type::BITS - $prime.leading_zeros();
+        let mask = $type::MAX >> ($type::BITS - k);
+        (k, mask, mask - $prime + 1)
+    }};
+}
+
+macro_rules! impl_residue {
+    ($base_type:ident, $larger_type:ident) => {
+        impl QuadraticResidue for $base_type {
+            #[inline]
+            fn residue(self, prime: Self) -> Self {
+                self.mul_mod(self, prime)
+            }
+
+            #[inline]
+            fn mul_mod(self, other: Self, prime: Self) -> Self {
+                Const::<Self>::mul_mod(self, other, prime)
+            }
+
+            const_arithmetic!();
+        }
+
+        impl Const<$base_type> {
+            /// Compute `(a * b) % prime` by folding the double width product, without division.
+            ///
+            /// For `prime = 2^k - c`, where `2^k` is the smallest power of two above the prime,
+            /// `2^k = c mod prime`, and so the bits of `x` above `k` fold down as
+            /// `(x >> k) * c + (x & (2^k - 1))`. Each fold shrinks `x` by a factor of at least
+            /// `2^k / c >= 2`. Two folds reduce the product of two values below the prime to at
+            /// most `2^k + c^2`, so with `c < 2^(k/2)`, as for every prime of the prime table, the
+            /// loop folds at most twice more. As `prime >= 2^(k-1)`, at most one final subtraction
+            /// is needed. Primes with a larger `c`, further below the power of two, divide instead.
+            #[inline(always)]
+            pub(crate) const fn mul_mod(a: $base_type, b: $base_type, prime: $base_type) -> $base_type {
+                let (k, mask, c) = fold_params!($base_type, prime);
+                if c >> (k / 2) != 0 {
+                    return (a as $larger_type * b as $larger_type % prime as $larger_type) as $base_type;
+                }
+                let (mask, c) = (mask as $larger_type, c as $larger_type);
+                let mut x = a as $larger_type * b as $larger_type;
+                x = (x >> k) * c + (x & mask);
+                x = (x >> k) * c + (x & mask);
+                while x > mask {
+                    x = (x >> k) * c + (x & mask);
+                }
+                let x = x as $base_type;
+                if x >= prime { x - prime } else { x }
+            }
+        }
+    };
+}
+
+macro_rules! impl_residue_wide {
+    ($base_type:ident) => {
+        impl QuadraticResidue for $base_type {
+            #[inline]
+            fn residue(self, prime: Self) -> Self {
+                self.mul_mod(self, prime)
+            }
+
+            #[inline]
+            fn mul_mod(self, other: Self, prime: Self) -> Self {
+                Const::<Self>::mul_mod(self, other, prime)
+            }
+
+            const_arithmetic!();
+        }
+
+        impl Const<$base_type> {
+            /// Compute `(a * b) % prime` by folding the u128 product, without division.
+            ///
+            /// Shifting a u128 by a variable `k` is expensive, so the high half of the product is
+            /// first folded at the constant 64 bit boundary with `2^64 = c * 2^(64 - k) mod prime`,
+            /// and only the remaining 64 bits are folded at `k` as in the smaller implementations.
+            /// With `c < 2^(k/2)` each loop runs at most a few times, and primes with a larger `c`
+            /// are reduced by the digit division of the u128 implementation instead.
+            #[inline(always)]
+            pub(crate) const fn mul_mod(a: $base_type, b: $base_type, prime: $base_type) -> $base_type {
+                let (k, mask, c) = fold_params!($base_type, prime);
+                if c >> (k / 2) != 0 {
+                    return reduce_u256_digits(0, a as u128 * b as u128, prime as u128) as $base_type;
+                }
+                let fold = (c as u128) << ($base_type::BITS - k);
+                let mut x = a as u128 * b as u128;
+                while x > $base_type::MAX as u128 {
+                    x = (x >> $base_type::BITS) * fold + (x as $base_type as u128);
+                }
+                let mut x = x as $base_type;
+                while x > mask {
+                    x = (x >> k) * c + (x & mask);
+                }
+                if x >= prime { x - prime } else { x }
+            }
+        }
+    };
+}
+
+impl_residue!(u8, u16);
+impl_residue!(u16, u32);
+impl_residue!(u32, u64);
+impl_residue_wide!(u64);
+#[cfg(target_pointer_width = "64")]
+impl_residue_wide!(usize);
+#[cfg(target_pointer_width = "32")]
+impl_residue!(usize, u64);
+#[cfg(not(any(target_pointer_width = "64", target_pointer_width = "32")))]
+compile_error!("Unsupported pointer width.");
+
+impl QuadraticResidue for u128 {
+    #[inline(always)]
+    fn residue(self, prime: Self) -> Self {
+        self.mul_mod(self, prime)
+    }
+
+    #[inline(always)]
+    fn mul_mod(self, other: Self, prime: Self) -> Self {
+        Const::<Self>::mul_mod(self, other, prime)
+    }
+
+    const_arithmetic!();
+}
+
+impl Const<u128> {
+    /// Compute `(a * b) % prime` through the full 256-bit product.
+    #[inline(always)]
+    pub(crate) const fn mul_mod(a: u128, b: u128, prime: u128) -> u128 {
+        let (high, low) = widening_mul_u128(a, b);
+        reduce_u256(high, low, prime)
+    }
+}
+
+/// Compute the full 256-bit product of two u128 values, as the `(high, low)` halves.
+#[inline]
+const fn widening_mul_u128(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & MASK);
+    let (b_high, b_low) = (b >> 64, b & MASK);
+
+    // each partial product of two 64-bit halves fits in a u128
+    let low_low = a_low * b_low;
+    let low_high = a_low * b_high;
+    let high_low = a_high * b_low;
+    let high_high = a_high * b_high;
+
+    let middle = (low_low >> 64) + (low_high & MASK) + (high_low & MASK);
+    let low = (low_low & MASK) | (middle << 64);
+    let high = high_high + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+    (high, low)
+}
+
+/// Compute `(high * 2^128 + low) % prime` without 256-bit division.
+///
+/// Folds the high half at the 128 bit boundary with `2^128 = c * 2^(128 - k) mod prime`, and then
+/// the low half at `k`, as in the 64-bit [QuadraticResidue::mul_mod] implementations. Each fold
+/// only shrinks the value by a factor of `2^k / c`, so the primes just below a power of two, such as
+/// the primes of every domain, take a couple of folds, while the primes closer to `2^(k-1)`, with
+/// `c > 2^(k/2)`, are reduced by [reduce_u256_digits] instead.
+#[inline]
+const fn reduce_u256(mut high: u128, mut low: u128, prime: u128) -> u128 {
+    let (k, mask, c) = fold_params!(u128, prime);
+    if c >> (k / 2) != 0 {
+        return reduce_u256_digits(high, low, prime);
+    }
+    let fold = c << (128 - k);
+    while high != 0 {
+        let (carry_high, carry_low) = widening_mul_u128(high, fold);
+        let (sum, overflow) = low.overflowing_add(carry_low);
+        high = carry_high + overflow as u128;
+        low = sum;
+    }
+    while low > mask {
+        low = (low >> k) * c + (low & mask);
+    }
+    if low >= prime { low - prime } else { low }
+}
+
+/// Compute `(high * 2^128 + low) % prime` one 64-bit digit at a time.
+///
+/// The value and prime are shifted so the top bit of the prime is set, and each digit is divided
+/// in with the 3-by-2 digit division of Möller and Granlund, "Improved division by invariant
+/// integers", which estimates the quotient from a reciprocal of the prime rather than dividing.
+/// The reciprocal takes a single 128 by 64 bit division per call.
+#[cold]
+#[inline(never)]
+const fn reduce_u256_digits(high: u128, low: u128, prime: u128) -> u128 {
+    let shift = prime.leading_zeros();
+    let divisor = prime << shift;
+    let reciprocal = reciprocal_3by2(divisor);
+
+    // the digits of `(high * 2^128 + low) << shift`, most significant first
+    let (top, high, low) = match shift {
+        0 => (0, high, low),
+        _ => (high >> (128 - shift), (high << shift) | (low >> (128 - shift)), low << shift),
+    };
+    let digits = [(top >> 64) as u64, top as u64, (high >> 64) as u64, high as u64, (low >> 64) as u64, low as u64];
+
+    let mut remainder = 0;
+    let mut i = 0;
+    while i < digits.len() {
+        remainder = remainder_3by2(remainder, digits[i], divisor, reciprocal);
+        i += 1;
+    }
+    remainder >> shift
+}
+
+/// The reciprocal `floor((2^192 - 1) / divisor) - 2^64` of a 128-bit divisor with its top bit set.
+const fn reciprocal_3by2(divisor: u128) -> u64 {
+    let (d1, d0) = ((divisor >> 64) as u64, divisor as u64);
+
+    // the reciprocal of the high digit, `floor((2^128 - 1) / d1) - 2^64`, adjusted for the low digit
+    let mut v = (u128::MAX / d1 as u128) as u64;
+    let mut p = d1.wrapping_mul(v).wrapping_add(d0);
+    if p < d0 {
+        v = v.wrapping_sub(1);
+        if p >= d1 {
+            v = v.wrapping_sub(1);
+            p = p.wrapping_sub(d1);
+        }
+        p = p.wrapping_sub(d1);
+    }
+
+    let t = v as u128 * d0 as u128;
+    let (t1, t0) = ((t >> 64) as u64, t as u64);
+    p = p.wrapping_add(t1);
+    if p < t1 {
+        v = v.wrapping_sub(1);
+        if ((p as u128) << 64 | t0 as u128) >= divisor {
+            v = v.wrapping_sub(1);
+        }
+    }
+    v
+}
+
+/// The remainder of `(remainder * 2^64 + digit) / divisor`, for a `remainder < divisor` and a
+/// divisor with its top bit set.
+#[inline(always)]
+const fn remainder_3by2(remainder: u128, digit: u64, divisor: u128, reciprocal: u64) -> u128 {
+    let (u2, u1) = ((remainder >> 64) as u64, remainder as u64);
+    let (d1, d0) = ((divisor >> 64) as u64, divisor as u64);
+
+    // estimate the quotient digit, which is at most one too large, or rarely one too small
+    let q = (reciprocal as u128 * u2 as u128).wrapping_add(remainder);
+    let (q1, q0) = ((q >> 64) as u64, q as u64);
+    let r1 = u1.wrapping_sub(q1.wrapping_mul(d1));
+    let t = d0 as u128 * q1 as u128;
+    let mut r = ((r1 as u128) << 64 | digit as u128).wrapping_sub(t).wrapping_sub(divisor);
+    if (r >> 64) as u64 >= q0 {
+        r = r.wrapping_add(divisor);
+    }
+    if r >= divisor {
+        r -= divisor;
+    }
+    r
+}
+
+/// The builders the tests of each sequence type run over, covering every [Backend],
+/// [KeySchedule] and [RandomSequenceBuilder::fold_tail] setting, over the full range, the smallest
+/// domains, a stepped range and a domain of half the bits of `T`.
+#[cfg(test)]
+pub(crate) fn test_configs<T>() -> std::vec::Vec<RandomSequenceBuilder<T>>
+where
+    T: QuadraticResidue,
+    RandomSequenceBuilder<T>: RandomSequenceBuilderInit<T>,
+{
+    let int = |value: u8| T::from_u8(value).unwrap();
+    let config = <RandomSequenceBuilder<T> as RandomSequenceBuilderInit<T>>::new(int(7), int(11));
+    let seeded = config.with_key_schedule(KeySchedule::Seeded);
+    let half = T::max_value() >> (type_bits::<T>() / 2) as usize;
+    std::vec![
+        config,
+        config.with_domain(int(100)),
+        config.with_domain(int(1)),
+        config.with_domain(int(3)),
+        config.with_domain(int(7)),
+        config.with_range_step(int(10)..=int(120), int(3)),
+        config.with_rounds(1),
+        config.with_rounds(5).with_domain(int(77)),
+        seeded,
+        config.with_fold_tail(true),
+        config.with_fold_tail(true).with_rounds(3).with_domain(int(77)),
+        seeded.with_fold_tail(true),
+        seeded.with_fold_tail(true).with_domain(int(100)),
+        seeded.with_fold_tail(true).with_rounds(4).with_domain(half),
+        config.with_backend(Backend::Feistel { rounds: 6 }),
+        config.with_backend(Backend::Feistel { rounds: 4 }).with_domain(int(50)),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::Entry;
+    use std::collections::{HashMap, HashSet};
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    use bnum::types::{U128, U256, U512};
+    use num_traits::AsPrimitive;
+    use rand::rngs::OsRng;
+    use rand::Rng;
+
+    use super::*;
+
+    /// Check the prime satisfies `p = 3 mod 4`.
+    fn is_3_mod_4(n: u64) -> bool {
+        n % 4 == 3
+    }
+
+    fn is_send<T: Send>() {}
+    fn is_sync<T: Sync>() {}
+
+    macro_rules! test_config {
+        ($name:ident, $type:ident, $check:literal) => {
+            #[test]
+            fn $name() {
+                let config = RandomSequenceBuilder::<$type>::new(0, 0);
+                let config_orig = config.clone();
+
+                // check the configured prime number satisfies the requirements
+                // is_prime crate makes this very quick for u64
+                let is_prime_res = is_prime::is_prime(&config.prime.to_string());
+                let is_3_mod_4_res = is_3_mod_4(config.prime as u64);
+
+                let mut found_number = config.prime;
+                if !is_prime_res || !is_3_mod_4_res {
+                    if found_number % 2 == 0 {
+                        found_number -= 1;
+                    }
+
+                    // suggest a suitable prime number (slow, but only run when there's a bad prime)
+                    let mut found_next_prime = false;
+                    let mut found_next_3_mod_4 = false;
+
+                    while !found_next_prime || !found_next_3_mod_4 {
+                        found_number -= 2;
+                        found_next_prime = is_prime::is_prime(&found_number.to_string());
+                        found_next_3_mod_4 = is_3_mod_4(found_number as u64);
                     }
                 }
 
@@ -265,7 +1444,7 @@ mod tests {
 
                 // check config can be cloned and equality tested
                 let sequence = config.into_iter();
-                assert_eq!(sequence.config, config_orig);
+                assert_eq!(*sequence.config(), config_orig);
 
                 // check permute_qpr for uniqueness
                 const CHECK: usize = $check;
@@ -283,6 +1462,11 @@ mod tests {
                 }
                 assert_eq!(nums.len(), (0..CHECK).len());
 
+                // check invert_qpr reverses permute_qpr, including the values mapped to themselves
+                for i in (0..CHECK).map(|i| i as $type).chain(config.prime - 2..=$type::MAX) {
+                    assert_eq!(config.invert_qpr(config.permute_qpr(i)), i, "Failed to invert {}", i);
+                }
+
                 // check builder is send and sync
                 is_send::<RandomSequenceBuilder<$type>>();
                 is_sync::<RandomSequenceBuilder<$type>>();
@@ -294,4 +1478,741 @@ mod tests {
     test_config!(test_u16_config, u16, 65536);
     test_config!(test_u32_config, u32, 100_000);
     test_config!(test_u64_config, u64, 100_000);
+    test_config!(test_u128_config, u128, 100_000);
+
+    /// Construct a builder generically, through [RandomSequenceBuilderInit].
+    fn init<T>(seed_base: T, seed_offset: T) -> RandomSequenceBuilder<T>
+    where
+        T: QuadraticResidue,
+        RandomSequenceBuilder<T>: RandomSequenceBuilderInit<T>,
+    {
+        RandomSequenceBuilder::<T>::new(seed_base, seed_offset)
+    }
+
+    macro_rules! test_init {
+        ($name:ident, $type:ident) => {
+            #[test]
+            fn $name() {
+                type Builder = RandomSequenceBuilder<$type>;
+                let config = init::<$type>(7, 11);
+                assert_eq!(config, Builder::new(7, 11));
+                assert_eq!(config.init_base, <Builder as RandomSequenceBuilderInit<$type>>::INIT_BASE);
+                assert_eq!(config.init_offset, <Builder as RandomSequenceBuilderInit<$type>>::INIT_OFFSET);
+                assert_eq!(config.prime, <Builder as RandomSequenceBuilderInit<$type>>::PRIME);
+                assert_eq!(config.intermediate_xor, <Builder as RandomSequenceBuilderInit<$type>>::INTERMEDIATE_XOR);
+                assert_eq!(config.validate(), Ok(()));
+            }
+        };
+    }
+
+    test_init!(test_u8_init, u8);
+    test_init!(test_u16_init, u16);
+    test_init!(test_u32_init, u32);
+    test_init!(test_u64_init, u64);
+    test_init!(test_usize_init, usize);
+    test_init!(test_u128_init, u128);
+
+    #[test]
+    fn test_u128_mul_mod() {
+        const PRIME: u128 = 340282366920938463463374607431768211283;
+        const SMALL_PRIME: u128 = 170141183460469231731687303715884105727;  // 2^127 - 1, c doesn't fit in 64 bits
+        const OTHER_PRIME: u128 = 79228162514264337593543950319;  // 2^96 - 17
+
+        // reference values computed with arbitrary precision integers
+        let cases: [(u128, u128, u128, u128); 7] = [
+            (3, 5, PRIME, 15),
+            (PRIME - 1, PRIME - 1, PRIME, 1),
+            (1 << 127, 1 << 127, PRIME, 85070591730234615865843651857942060303),
+            (0x5bf0363546790905682f01615bf03635, 0x46790905682f01615bf0363546790905, PRIME, 290156461157561732128435621653175668969),
+            (SMALL_PRIME - 2, SMALL_PRIME - 3, SMALL_PRIME, 6),
+            (0x5bf0363546790905682f01615bf03635, 0x46790905682f01615bf0363546790905, SMALL_PRIME, 152089219362479051587963768427134571688),
+            (0x5bf0363546790905682f0161, 0x46790905682f01615bf03635, OTHER_PRIME, 2613958820738799033420043052),
+        ];
+        for (a, b, prime, expected) in cases {
+            assert_eq!(a.mul_mod(b, prime), expected, "{} * {} % {}", a, b, prime);
+            assert_eq!(b.mul_mod(a, prime), expected, "{} * {} % {}", b, a, prime);
+        }
+    }
+
+    #[test]
+    fn test_reduce_u256_digits() {
+        let reference = |high: u128, low: u128, prime: u128| -> u128 {
+            let value = (U256::from(high) << 128u32) | U256::from(low);
+            (value % U256::from(prime)).as_()
+        };
+
+        // the digit division agrees with the folds for the primes just below a power of two, and
+        // with a 256-bit reference for any value and modulus, including a high half above it
+        let mut rng = OsRng;
+        let mut moduli = Vec::from([3, 7, (1 << 64) + 1, u64::MAX as u128, 1 << 127, u128::MAX, 340282366920938463463374607431768211283]);
+        moduli.extend((0..32).map(|_| (rng.gen::<u128>() >> rng.gen_range(0..126)).max(1)));
+        for modulus in moduli {
+            for _ in 0..32 {
+                let (high, low) = (rng.gen::<u128>() >> rng.gen_range(0..128), rng.gen::<u128>());
+                let expected = reference(high, low, modulus);
+                assert_eq!(reduce_u256_digits(high, low, modulus), expected, "{} * 2^128 + {} % {}", high, low, modulus);
+                assert_eq!(reduce_u256(high, low, modulus), expected, "{} * 2^128 + {} % {}", high, low, modulus);
+            }
+        }
+    }
+
+    /// Compute `(a * b) % modulus` by double-and-add, a slow reference that can't overflow.
+    fn mul_mod_reference(a: u128, b: u128, modulus: u128) -> u128 {
+        let add_mod = |x: u128, y: u128| if x >= modulus - y { x - (modulus - y) } else { x + y };
+        let (mut result, mut a, mut b) = (0, a % modulus, b);
+        while b > 0 {
+            if b & 1 == 1 {
+                result = add_mod(result, a);
+            }
+            a = add_mod(a, a);
+            b >>= 1;
+        }
+        result
+    }
+
+    macro_rules! test_mul_mod {
+        ($name:ident, $type:ident) => {
+            #[test]
+            fn $name() {
+                // the prime of every domain, and the moduli too far below a power of two to fold
+                let moduli = (2..=$type::BITS)
+                    .map(|bits| crate::primes::prime_for_bits(bits) as $type)
+                    .chain((1..$type::BITS).map(|bits| (1 << bits) + 1))
+                    .chain([1, 2, 3, $type::MAX, $type::MAX - 1, $type::MAX / 3]);
+
+                let mut rng = OsRng;
+                for modulus in moduli {
+                    let values: Vec<$type> = [0, 1, 2, modulus - 1, modulus / 2, $type::MAX, $type::MAX - 1]
+                        .into_iter()
+                        .chain((0..32).map(|_| rng.gen::<$type>()))
+                        .collect();
+                    for (a, b) in values.iter().copied().zip(values.iter().copied().rev()) {
+                        let expected = mul_mod_reference(a as u128, b as u128, modulus as u128) as $type;
+                        assert_eq!(a.mul_mod(b, modulus), expected, "{} * {} % {}", a, b, modulus);
+                        assert_eq!(a.residue(modulus), mul_mod_reference(a as u128, a as u128, modulus as u128) as $type);
+                    }
+                }
+            }
+        };
+    }
+
+    test_mul_mod!(test_u8_mul_mod, u8);
+    test_mul_mod!(test_u16_mul_mod, u16);
+    test_mul_mod!(test_u32_mul_mod, u32);
+    test_mul_mod!(test_u64_mul_mod, u64);
+    test_mul_mod!(test_usize_mul_mod, usize);
+    test_mul_mod!(test_u128_mul_mod_reference, u128);
+
+    /// The fixed points of `f` over `0..len`, and the correlation coefficient of `f(x)` with `x`.
+    fn fixed_points_and_correlation(len: u64, f: impl Fn(u64) -> u64) -> (Vec<u64>, f64) {
+        let mut fixed_points = Vec::new();
+        let (mut sum_x, mut sum_y, mut sum_xx, mut sum_yy, mut sum_xy) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for x in 0..len {
+            let y = f(x);
+            if x == y {
+                fixed_points.push(x);
+            }
+            let (x, y) = (x as f64, y as f64);
+            sum_x += x;
+            sum_y += y;
+            sum_xx += x * x;
+            sum_yy += y * y;
+            sum_xy += x * y;
+        }
+        let n = len as f64;
+        let correlation = (n * sum_xy - sum_x * sum_y) / ((n * sum_xx - sum_x * sum_x).sqrt() * (n * sum_yy - sum_y * sum_y).sqrt());
+        (fixed_points, correlation)
+    }
+
+    macro_rules! test_fold_tail {
+        ($name:ident, $type:ident, [$($domain:expr),+], $small_prime:expr) => {
+            #[test]
+            fn $name() {
+                // each domain with its largest prime, and the full range with a smaller prime
+                let domains = [None, $(Some($domain)),+].map(|domain| (domain, None));
+                for (domain, prime) in domains.into_iter().chain([(None, Some($small_prime))]) {
+                    let builder = |seed_base, seed_offset| {
+                        let config = match domain {
+                            Some(domain) => RandomSequenceBuilder::<$type>::new(seed_base, seed_offset).with_domain(domain),
+                            None => RandomSequenceBuilder::<$type>::new(seed_base, seed_offset),
+                        }
+                        .with_fold_tail(true);
+                        RandomSequenceBuilder { prime: prime.unwrap_or(config.prime), ..config }
+                    };
+                    let config = builder(0, 0);
+                    assert_eq!(config.validate(), Ok(()));
+                    let mask = config.mask();
+                    let len = config.max_index() as u64 + 1;
+                    let tail_window = config.tail_window();
+                    assert!(tail_window.is_some());
+
+                    // the legacy pass maps the tail to itself, the folded pass scrambles all of it,
+                    // including the larger tail of a smaller prime
+                    for x in config.prime..=mask {
+                        assert_eq!(config.with_fold_tail(false).permute_pass(x, None), x);
+                        assert_ne!(config.permute_pass(x, tail_window), x, "{} is a fixed point", x);
+                    }
+
+                    // check each pass is still a permutation of the range, and inverts
+                    let mut seen = [false; 1 << $type::BITS];
+                    for x in 0..=mask {
+                        let y = config.permute_pass(x, tail_window);
+                        assert!(y <= mask && !seen[y as usize], "Duplicate value {}", y);
+                        seen[y as usize] = true;
+                        assert_eq!(config.invert_pass(y, tail_window), x, "Failed to invert {}", x);
+                    }
+
+                    for (seed_base, seed_offset) in [(0, 0), (7, 11), (123, 99)] {
+                        for rounds in [1, 2, 3] {
+                            let config = builder(seed_base, seed_offset).with_rounds(rounds);
+                            let keys = config.keys();
+                            let permute = |x: u64| config.permute(x as $type, &keys) as u64;
+                            let (fixed_points, correlation) = fixed_points_and_correlation(len, permute);
+
+                            // a single round fixes only the fixed points 0 and 1 of the residue, while
+                            // the wide window of a smaller prime can map a value back to itself
+                            if rounds == 1 {
+                                if prime.is_none() {
+                                    assert_eq!(fixed_points, [0, 1]);
+                                }
+                                continue;
+                            }
+
+                            // about one fixed point is expected of a random permutation
+                            assert!(fixed_points.len() <= 4, "{} fixed points with {} rounds", fixed_points.len(), rounds);
+
+                            // the correlation of a random permutation has a standard deviation of 1/sqrt(len)
+                            let limit = 4.0 / (len as f64).sqrt();
+                            assert!(correlation.abs() < limit, "Correlation {} with {} rounds", correlation, rounds);
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    test_fold_tail!(test_u8_fold_tail, u8, [12, 100, 200], 127);
+    test_fold_tail!(test_u16_fold_tail, u16, [1000, 40000], 8191);
+
+    #[test]
+    fn test_try_from_spec() {
+        let spec = |prime| RandomSequenceBuilder::<u32>::try_from_spec(7, 11, 0x682f0161, 0x46790905, prime, 0x5bf03635);
+        let config = spec(4294967291).unwrap();
+        assert_eq!(config.validate(), Ok(()));
+        assert!(config.into_iter().take(1000).collect::<HashSet<u32>>().len() == 1000);
+
+        assert_eq!(spec(4294967295).unwrap_err(), Error::NotPrime);
+        assert_eq!(spec(4294967289).unwrap_err(), Error::NotThreeModFour);
+        assert!(spec(4294967279).is_ok());
+        assert_eq!(spec(2147483647).unwrap().validate(), Ok(()));
+        assert_eq!(spec(3).unwrap().validate(), Ok(()));
+        let config = RandomSequenceBuilder::<u32>::new(7, 11).with_domain(1000);
+        assert_eq!(RandomSequenceBuilder { prime: 1031, ..config }.validate(), Err(Error::PrimeOutOfRange));
+
+        // a prime below the upper half of the range, as earlier versions accepted, still permutes
+        for fold_tail in [false, true] {
+            let config = RandomSequenceBuilder::<u16>::try_from_spec(7, 11, 0x6829, 0x4679, 8191, 0x5bf0).unwrap();
+            let sequence = config.with_fold_tail(fold_tail).into_iter();
+            assert_eq!(sequence.collect::<HashSet<u16>>().len(), 1 << 16);
+        }
+
+        // the table primes of every width are valid
+        assert!(RandomSequenceBuilder::<u8>::try_from_spec(0, 0, 0, 0, 251, 0).is_ok());
+        assert!(RandomSequenceBuilder::<u8>::try_from_spec(0, 0, 0, 0, 253, 0).is_err());
+        assert!(RandomSequenceBuilder::<u64>::try_from_spec(0, 0, 0, 0, 18446744073709551427, 0).is_ok());
+        assert!(RandomSequenceBuilder::<u128>::try_from_spec(0, 0, 0, 0, 340282366920938463463374607431768211283, 0).is_ok());
+        assert!(RandomSequenceBuilder::<u128>::try_from_spec(0, 0, 0, 0, 340282366920938463463374607431768211287, 0).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = RandomSequenceBuilder::<u16>::new(7, 11).with_range_step(100..=50_000, 7);
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(RandomSequenceBuilder::<u16>::new(7, 11).with_bits(3).validate(), Ok(()));
+        assert_eq!(RandomSequenceBuilder::<u16>::new(7, 11).with_signed_range(-1000..=1000).validate(), Ok(()));
+        assert_eq!(RandomSequenceBuilder::<u16>::new(7, 11).with_signed_range(i16::MIN..=i16::MAX).validate(), Ok(()));
+
+        let invalid = [
+            (RandomSequenceBuilder { domain: Some(0), ..config }, Error::EmptyDomain),
+            (RandomSequenceBuilder { range_step: 0, ..config }, Error::InvalidRange),
+            (RandomSequenceBuilder { range_step: 10, ..config }, Error::InvalidRange),
+            (RandomSequenceBuilder { range_start: 20_000, ..config }, Error::InvalidRange),
+            (RandomSequenceBuilder { rounds: 0, ..config }, Error::ZeroRounds),
+            (RandomSequenceBuilder { backend: Backend::Feistel { rounds: 0 }, ..config }, Error::ZeroRounds),
+            (RandomSequenceBuilder { prime: 65519, ..config }, Error::PrimeOutOfRange),
+            (RandomSequenceBuilder { prime: 8187, ..config }, Error::NotPrime),
+        ];
+        for (config, error) in invalid {
+            assert_eq!(config.validate(), Err(error));
+        }
+
+        // no window of the range holds the tail of the smallest primes
+        let config = RandomSequenceBuilder::<u16>::new(7, 11);
+        assert_eq!(RandomSequenceBuilder { prime: 3, ..config }.validate(), Ok(()));
+        assert_eq!(RandomSequenceBuilder { prime: 3, ..config.with_fold_tail(true) }.validate(), Err(Error::PrimeTooSmall));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_validation() {
+        let config = RandomSequenceBuilder::<u32>::new(7, 11).with_domain(1000);
+        let json = serde_json::to_value(config).unwrap();
+        assert_eq!(serde_json::from_value::<RandomSequenceBuilder<u32>>(json.clone()).unwrap(), config);
+
+        // builders serialized before the newer fields existed keep their legacy behaviour
+        let mut legacy = json.clone();
+        for field in ["backend", "rounds", "key_schedule", "fold_tail"] {
+            legacy.as_object_mut().unwrap().remove(field);
+        }
+        let legacy: RandomSequenceBuilder<u32> = serde_json::from_value(legacy).unwrap();
+        assert_eq!(legacy.key_schedule, KeySchedule::Legacy);
+        assert!(!legacy.fold_tail);
+        assert_eq!(legacy.rounds, DEFAULT_ROUNDS);
+
+        // tampered parameters fail to deserialize
+        for (field, value, error) in [
+            ("prime", 1003, Error::NotPrime),
+            ("prime", 1021, Error::NotThreeModFour),
+            ("prime", 2039, Error::PrimeOutOfRange),
+            ("domain", 0, Error::EmptyDomain),
+            ("range_step", 0, Error::InvalidRange),
+            ("range_step", 1 << 23, Error::InvalidRange),
+            ("rounds", 0, Error::ZeroRounds),
+        ] {
+            let mut tampered = json.clone();
+            tampered[field] = value.into();
+            let result = serde_json::from_value::<RandomSequenceBuilder<u32>>(tampered);
+            assert_eq!(result.unwrap_err().to_string(), error.to_string(), "{} = {}", field, value);
+        }
+
+        // builders of any deserializable type deserialize, without requiring Default
+        fn is_deserialize<'de, T: QuadraticResidue + serde::Deserialize<'de>>() {
+            fn check<'de, D: serde::Deserialize<'de>>() {}
+            check::<RandomSequenceBuilder<T>>();
+        }
+        is_deserialize::<u32>();
+    }
+
+    /// A user-defined 256-bit integer, reducing through its 512-bit product.
+    impl QuadraticResidue for U256 {
+        fn residue(self, prime: Self) -> Self {
+            self.mul_mod(self, prime)
+        }
+
+        fn mul_mod(self, other: Self, prime: Self) -> Self {
+            let product: U512 = AsPrimitive::<U512>::as_(self) * AsPrimitive::<U512>::as_(other);
+            (product % AsPrimitive::<U512>::as_(prime)).as_()
+        }
+    }
+
+    impl SequenceInt for U256 {
+        type Unsigned = U256;
+
+        fn to_unsigned(self) -> Self::Unsigned {
+            self
+        }
+
+        fn from_unsigned(value: Self::Unsigned) -> Self {
+            value
+        }
+    }
+
+    #[test]
+    fn test_wide_type() {
+        // 2^256 - 189, the largest 256-bit prime, which is 3 mod 4
+        let prime = U256::MAX - U256::from(188u8);
+        let config = RandomSequenceBuilder::<U256>::try_from_spec(
+            U256::from(7u8),
+            U256::from(11u8),
+            U256::from(0x682f01615bf03635u64) << 192,
+            U256::from(0x46790905682f0161u64) << 128,
+            prime,
+            U256::from(0x5bf0363546790905u64) << 64,
+        )
+        .unwrap()
+        .with_key_schedule(KeySchedule::Seeded)
+        .with_fold_tail(true);
+
+        let sequence = RandomSequence::<U256>::from(config);
+        let mut nums = HashSet::new();
+        for index in (0..100u8).map(U256::from).chain([prime - U256::ONE, prime, U256::MAX]) {
+            let value = sequence.n(index);
+            assert!(nums.insert(value), "Duplicate value {}", value);
+            assert_eq!(sequence.index_of(value), index);
+        }
+
+        // domains up to 128 bits use the prime table, and wider domains search for their prime
+        let config = config.with_domain(U256::from(1000u16));
+        assert_eq!(config.prime, U256::from(1019u16));
+        let mut sequence = RandomSequence::<U256>::from(config);
+        let nums: HashSet<U256> = core::iter::from_fn(|| sequence.next()).collect();
+        assert_eq!(nums.len(), 1000);
+        assert!(nums.iter().all(|num| *num < U256::from(1000u16)));
+
+        let config = config.with_domain(U256::ONE << 200);
+        assert_eq!(config.validate(), Ok(()));
+        assert!(config.prime > U256::ONE << 199);
+        let sequence = RandomSequence::<U256>::from(config);
+        let value = sequence.n(U256::from(5u8));
+        assert!(value < U256::ONE << 200);
+        assert_eq!(sequence.index_of(value), U256::from(5u8));
+
+        // the other backends permute values as a u128, so reject a wider type
+        let feistel = RandomSequenceBuilder { backend: Backend::Feistel { rounds: 6 }, ..config };
+        assert_eq!(feistel.validate(), Err(Error::BackendUnsupported));
+    }
+
+    #[test]
+    #[should_panic(expected = "The backend only supports types up to 128 bits.")]
+    fn test_wide_type_backend() {
+        let prime = U256::MAX - U256::from(188u8);
+        let config = RandomSequenceBuilder::<U256>::try_from_spec(U256::ONE, U256::ONE, U256::ONE, U256::ONE, prime, U256::ONE);
+        let _ = config.unwrap().with_backend(Backend::Feistel { rounds: 6 });
+    }
+
+    /// A user-defined 127-bit integer, whose largest value `2^127 - 1` is a Mersenne prime.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct U127(u128);
+
+    impl U127 {
+        const MASK: u128 = u128::MAX >> 1;
+    }
+
+    macro_rules! u127_ops {
+        ($($trait:ident $method:ident |$a:ident, $b:ident| $op:expr;)+) => {
+            $(
+                impl core::ops::$trait for U127 {
+                    type Output = U127;
+
+                    fn $method(self, other: Self) -> Self {
+                        let ($a, $b) = (self.0, other.0);
+                        U127($op)
+                    }
+                }
+            )+
+        };
+    }
+
+    u127_ops! {
+        Add add |a, b| { assert!(a + b <= U127::MASK, "overflow"); a + b };
+        Sub sub |a, b| a.checked_sub(b).expect("overflow");
+        Mul mul |a, b| a.checked_mul(b).filter(|x| *x <= U127::MASK).expect("overflow");
+        Div div |a, b| a / b;
+        Rem rem |a, b| a % b;
+        BitAnd bitand |a, b| a & b;
+        BitOr bitor |a, b| a | b;
+        BitXor bitxor |a, b| a ^ b;
+    }
+
+    impl core::ops::Not for U127 {
+        type Output = U127;
+
+        fn not(self) -> Self {
+            U127(!self.0 & U127::MASK)
+        }
+    }
+
+    impl core::ops::Shl<usize> for U127 {
+        type Output = U127;
+
+        fn shl(self, bits: usize) -> Self {
+            U127((self.0 << bits) & U127::MASK)
+        }
+    }
+
+    impl core::ops::Shr<usize> for U127 {
+        type Output = U127;
+
+        fn shr(self, bits: usize) -> Self {
+            U127(self.0 >> bits)
+        }
+    }
+
+    impl num_traits::Zero for U127 {
+        fn zero() -> Self {
+            U127(0)
+        }
+
+        fn is_zero(&self) -> bool {
+            self.0 == 0
+        }
+    }
+
+    impl num_traits::One for U127 {
+        fn one() -> Self {
+            U127(1)
+        }
+    }
+
+    impl num_traits::Num for U127 {
+        type FromStrRadixErr = core::num::ParseIntError;
+
+        fn from_str_radix(value: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+            u128::from_str_radix(value, radix).map(|value| U127(value & U127::MASK))
+        }
+    }
+
+    impl num_traits::Bounded for U127 {
+        fn min_value() -> Self {
+            U127(0)
+        }
+
+        fn max_value() -> Self {
+            U127(U127::MASK)
+        }
+    }
+
+    impl num_traits::ToPrimitive for U127 {
+        fn to_i64(&self) -> Option<i64> {
+            self.0.to_i64()
+        }
+
+        fn to_u64(&self) -> Option<u64> {
+            self.0.to_u64()
+        }
+
+        fn to_u128(&self) -> Option<u128> {
+            Some(self.0)
+        }
+    }
+
+    impl FromPrimitive for U127 {
+        fn from_i64(value: i64) -> Option<Self> {
+            u128::try_from(value).ok().map(U127)
+        }
+
+        fn from_u64(value: u64) -> Option<Self> {
+            Some(U127(value as u128))
+        }
+
+        fn from_u128(value: u128) -> Option<Self> {
+            (value <= U127::MASK).then_some(U127(value))
+        }
+    }
+
+    impl num_traits::NumCast for U127 {
+        fn from<N: num_traits::ToPrimitive>(value: N) -> Option<Self> {
+            value.to_u128().and_then(<U127 as FromPrimitive>::from_u128)
+        }
+    }
+
+    impl num_traits::CheckedAdd for U127 {
+        fn checked_add(&self, other: &Self) -> Option<Self> {
+            Some(U127(self.0 + other.0)).filter(|x| x.0 <= U127::MASK)
+        }
+    }
+
+    impl num_traits::CheckedSub for U127 {
+        fn checked_sub(&self, other: &Self) -> Option<Self> {
+            self.0.checked_sub(other.0).map(U127)
+        }
+    }
+
+    impl num_traits::CheckedMul for U127 {
+        fn checked_mul(&self, other: &Self) -> Option<Self> {
+            self.0.checked_mul(other.0).filter(|x| *x <= U127::MASK).map(U127)
+        }
+    }
+
+    impl num_traits::CheckedDiv for U127 {
+        fn checked_div(&self, other: &Self) -> Option<Self> {
+            self.0.checked_div(other.0).map(U127)
+        }
+    }
+
+    impl num_traits::Saturating for U127 {
+        fn saturating_add(self, other: Self) -> Self {
+            U127((self.0 + other.0).min(U127::MASK))
+        }
+
+        fn saturating_sub(self, other: Self) -> Self {
+            U127(self.0.saturating_sub(other.0))
+        }
+    }
+
+    impl WrappingAdd for U127 {
+        fn wrapping_add(&self, other: &Self) -> Self {
+            U127(self.0.wrapping_add(other.0) & U127::MASK)
+        }
+    }
+
+    impl WrappingSub for U127 {
+        fn wrapping_sub(&self, other: &Self) -> Self {
+            U127(self.0.wrapping_sub(other.0) & U127::MASK)
+        }
+    }
+
+    impl PrimInt for U127 {
+        fn count_ones(self) -> u32 {
+            self.0.count_ones()
+        }
+
+        fn count_zeros(self) -> u32 {
+            127 - self.0.count_ones()
+        }
+
+        fn leading_zeros(self) -> u32 {
+            self.0.leading_zeros() - 1
+        }
+
+        fn trailing_zeros(self) -> u32 {
+            self.0.trailing_zeros().min(127)
+        }
+
+        fn rotate_left(self, bits: u32) -> Self {
+            let bits = bits % 127;
+            U127(((self.0 << bits) | (self.0 >> ((127 - bits) % 127))) & U127::MASK)
+        }
+
+        fn rotate_right(self, bits: u32) -> Self {
+            self.rotate_left((127 - bits % 127) % 127)
+        }
+
+        fn signed_shl(self, bits: u32) -> Self {
+            self << bits as usize
+        }
+
+        fn signed_shr(self, bits: u32) -> Self {
+            let sign = self.0 >> 126;
+            U127(((self.0 | (sign * !U127::MASK)) as i128 >> bits) as u128 & U127::MASK)
+        }
+
+        fn unsigned_shl(self, bits: u32) -> Self {
+            self << bits as usize
+        }
+
+        fn unsigned_shr(self, bits: u32) -> Self {
+            self >> bits as usize
+        }
+
+        fn swap_bytes(self) -> Self {
+            unimplemented!("127-bit integers have no whole bytes")
+        }
+
+        fn from_be(value: Self) -> Self {
+            value.swap_bytes()
+        }
+
+        fn from_le(value: Self) -> Self {
+            value
+        }
+
+        fn to_be(self) -> Self {
+            self.swap_bytes()
+        }
+
+        fn to_le(self) -> Self {
+            self
+        }
+
+        fn pow(self, exponent: u32) -> Self {
+            (0..exponent).fold(U127(1), |x, _| x * self)
+        }
+    }
+
+    impl QuadraticResidue for U127 {
+        fn residue(self, prime: Self) -> Self {
+            self.mul_mod(self, prime)
+        }
+
+        fn mul_mod(self, other: Self, prime: Self) -> Self {
+            U127(self.0.mul_mod(other.0, prime.0))
+        }
+    }
+
+    impl SequenceInt for U127 {
+        type Unsigned = U127;
+
+        fn to_unsigned(self) -> Self::Unsigned {
+            self
+        }
+
+        fn from_unsigned(value: Self::Unsigned) -> Self {
+            value
+        }
+    }
+
+    #[test]
+    fn test_odd_width_type() {
+        // 2^127 - 1 = 3 mod 4 overflows when the strong Lucas test computes n + 1
+        let prime = <U127 as num_traits::Bounded>::max_value();
+        assert!(is_prime(prime));
+        assert_eq!(crate::primes::search_prime(prime), prime);
+        let config = RandomSequenceBuilder::<U127>::try_from_spec(U127(7), U127(11), U127(3), U127(5), prime, U127(9)).unwrap();
+
+        let sequence = RandomSequence::<U127>::from(config.with_key_schedule(KeySchedule::Seeded).with_fold_tail(true));
+        let mut nums = HashSet::new();
+        for index in (0..100).map(U127).chain([prime - U127(1), prime]) {
+            let value = sequence.n(index);
+            assert!(nums.insert(value), "Duplicate value {:?}", value);
+            assert_eq!(sequence.index_of(value), index);
+        }
+    }
+
+    /// A user-defined 64-bit integer, which permutes through the generic methods of
+    /// [QuadraticResidue] rather than the `const fn` arithmetic of `u64`.
+    type U64 = bnum::BUint<1>;
+
+    impl QuadraticResidue for U64 {
+        fn residue(self, prime: Self) -> Self {
+            self.mul_mod(self, prime)
+        }
+
+        fn mul_mod(self, other: Self, prime: Self) -> Self {
+            let product: U128 = AsPrimitive::<U128>::as_(self) * AsPrimitive::<U128>::as_(other);
+            (product % AsPrimitive::<U128>::as_(prime)).as_()
+        }
+    }
+
+    impl SequenceInt for U64 {
+        type Unsigned = U64;
+
+        fn to_unsigned(self) -> Self::Unsigned {
+            self
+        }
+
+        fn from_unsigned(value: Self::Unsigned) -> Self {
+            value
+        }
+    }
+
+    #[test]
+    fn test_generic_steps() {
+        // the generic methods of a custom type generate the same sequence as the const arithmetic of u64
+        type Init = RandomSequenceBuilder<u64>;
+        let config = RandomSequenceBuilder::<u64>::new(7, 11);
+        let custom = RandomSequenceBuilder::<U64>::try_from_spec(
+            U64::from(7u8),
+            U64::from(11u8),
+            U64::from(<Init as RandomSequenceBuilderInit<u64>>::INIT_BASE),
+            U64::from(<Init as RandomSequenceBuilderInit<u64>>::INIT_OFFSET),
+            U64::from(<Init as RandomSequenceBuilderInit<u64>>::PRIME),
+            U64::from(<Init as RandomSequenceBuilderInit<u64>>::INTERMEDIATE_XOR),
+        )
+        .unwrap();
+
+        let (seeded, custom_seeded) = (
+            config.with_key_schedule(KeySchedule::Seeded).with_fold_tail(true),
+            custom.with_key_schedule(KeySchedule::Seeded).with_fold_tail(true),
+        );
+        let pairs = [
+            (config, custom),
+            (config.with_domain(1000), custom.with_domain(U64::from(1000u16))),
+            (config.with_range_step(10..=10_000, 7), custom.with_range_step(U64::from(10u8)..=U64::from(10_000u16), U64::from(7u8))),
+            (config.with_rounds(5).with_domain(77), custom.with_rounds(5).with_domain(U64::from(77u8))),
+            (seeded, custom_seeded),
+            (seeded.with_bits(20), custom_seeded.with_bits(20)),
+            (config.with_backend(Backend::Feistel { rounds: 6 }).with_domain(50), custom.with_backend(Backend::Feistel { rounds: 6 }).with_domain(U64::from(50u8))),
+        ];
+        for (config, custom) in pairs {
+            let (sequence, custom_sequence) = (RandomSequence::<u64>::from(config), RandomSequence::<U64>::from(custom));
+            assert_eq!(custom_sequence.permutation.keys.start_index, U64::from(sequence.permutation.keys.start_index));
+            for index in (0..100).chain([u64::MAX / 3, u64::MAX]) {
+                let index = index.min(config.max_index());
+                let value = sequence.n(index);
+                assert_eq!(custom_sequence.n(U64::from(index)), U64::from(value), "{:?} at {}", config, index);
+                assert_eq!(custom_sequence.index_of(U64::from(value)), U64::from(index));
+            }
+        }
+    }
 }
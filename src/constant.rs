@@ -0,0 +1,318 @@
+use core::marker::PhantomData;
+
+use crate::builder::{Backend, Keys, KeySchedule, RandomSequenceBuilder};
+use crate::feistel;
+use crate::permutation::BuilderPermutation;
+#[cfg(target_arch = "x86_64")]
+use crate::permutation::DerivedLanes;
+use crate::primes::prime_for_bits;
+use crate::sequence::RandomSequence;
+
+/// The `const fn` implementations of the [RandomSequenceBuilder] permutation for each concrete
+/// integer type, which the generic methods can't provide as they go through `num_traits`.
+///
+/// The [QuadraticResidue](crate::QuadraticResidue) implementations of the primitive integers
+/// override the residue, tail window and round key methods with these functions, so the runtime
+/// and `const` sequences of each primitive share their arithmetic. The remaining steps mirror the
+/// generic methods of the builder, and the tests check `const_n` matches `n` for every
+/// configuration.
+pub(crate) struct Const<T>(PhantomData<T>);
+
+macro_rules! impl_const {
+    ($type:ident) => {
+        impl Const<$type> {
+            /// See [RandomSequenceBuilder::mask].
+            #[inline(always)]
+            pub(crate) const fn mask(config: &RandomSequenceBuilder<$type>) -> $type {
+                match config.domain {
+                    Some(domain) => {
+                        let bits = $type::BITS - (domain - 1).leading_zeros();
+                        let bits = if bits < 2 { 2 } else { bits };
+                        $type::MAX >> ($type::BITS - bits)
+                    }
+                    None => $type::MAX,
+                }
+            }
+
+            /// See [RandomSequenceBuilder::in_domain].
+            #[inline(always)]
+            pub(crate) const fn in_domain(config: &RandomSequenceBuilder<$type>, value: $type) -> bool {
+                match config.domain {
+                    Some(domain) => value < domain,
+                    None => true,
+                }
+            }
+
+            /// See [RandomSequenceBuilder::add_index].
+            #[inline(always)]
+            pub(crate) const fn add_index(config: &RandomSequenceBuilder<$type>, index: $type, offset: $type) -> $type {
+                match config.domain {
+                    Some(domain) => {
+                        let offset = offset % domain;
+                        if index >= domain - offset { index - (domain - offset) } else { index + offset }
+                    }
+                    None => index.wrapping_add(offset),
+                }
+            }
+
+            /// See [RandomSequenceBuilder::map_range].
+            #[inline(always)]
+            pub(crate) const fn map_range(config: &RandomSequenceBuilder<$type>, value: $type) -> $type {
+                config.range_start.wrapping_add(value * config.range_step)
+            }
+
+            /// See [QuadraticResidue::permute_qpr](crate::QuadraticResidue::permute_qpr).
+            #[inline(always)]
+            pub(crate) const fn permute_qpr(x: $type, prime: $type) -> $type {
+                if x >= prime {
+                    return x;
+                }
+                let residue = Const::<$type>::mul_mod(x, x, prime);
+                if x <= prime >> 1 { residue } else { prime - residue }
+            }
+
+            /// See [QuadraticResidue::tail_window](crate::QuadraticResidue::tail_window).
+            #[inline(always)]
+            pub(crate) const fn tail_window(config: &RandomSequenceBuilder<$type>) -> Option<($type, $type)> {
+                if !config.fold_tail {
+                    return None;
+                }
+
+                let mask = Self::mask(config);
+                let tail = mask - config.prime + 1;
+                let bits = $type::BITS - tail.leading_zeros();
+                let range_bits = mask.count_ones();
+                let tail_prime = if bits + 2 < range_bits {
+                    prime_for_bits(bits + 2) as $type
+                } else if config.prime > tail + 2 {
+                    config.prime
+                } else {
+                    prime_for_bits(range_bits) as $type
+                };
+                Some((mask - tail_prime + 1, tail_prime))
+            }
+
+            /// See the `permute_tail` of the builder.
+            #[inline(always)]
+            pub(crate) const fn permute_tail(y: $type, tail_window: Option<($type, $type)>) -> $type {
+                match tail_window {
+                    // the window rotated down by one, see the `rotate_window` of the builder
+                    Some((start, tail_prime)) if y >= start => {
+                        let last = tail_prime - 1;
+                        let local = Self::permute_qpr(if y == start { last } else { y - start - 1 }, tail_prime);
+                        if local == last { start } else { start + local + 1 }
+                    }
+                    _ => y,
+                }
+            }
+
+            /// See [RandomSequenceBuilder::permute_pass].
+            #[inline(always)]
+            const fn permute_pass(x: $type, prime: $type, tail_window: Option<($type, $type)>) -> $type {
+                Self::permute_tail(Self::permute_qpr(x, prime), tail_window)
+            }
+
+            /// See [QuadraticResidue::next_round_key](crate::QuadraticResidue::next_round_key).
+            #[inline(always)]
+            pub(crate) const fn next_round_key(offset: $type, xor: $type, intermediate_offset: $type) -> ($type, $type) {
+                let offset = offset.wrapping_add(intermediate_offset);
+                (offset, xor ^ offset)
+            }
+
+            /// See [RandomSequenceBuilder::derive_key].
+            #[inline(always)]
+            const fn derive_key(config: &RandomSequenceBuilder<$type>, seed: $type, init: $type) -> $type {
+                let mask = Self::mask(config);
+                let x = Self::permute_qpr(seed & mask, config.prime).wrapping_add(init) & mask;
+                Self::permute_qpr(x, config.prime)
+            }
+
+            /// See [RandomSequenceBuilder::keys].
+            pub(crate) const fn keys(config: &RandomSequenceBuilder<$type>) -> Keys<$type> {
+                let start_index = match config.domain {
+                    Some(domain) => Self::derive_key(config, config.seed_base, config.init_base) % domain,
+                    None => Self::derive_key(config, config.seed_base, config.init_base),
+                };
+
+                let (intermediate_offset, intermediate_xor) = match config.key_schedule {
+                    KeySchedule::Legacy => {
+                        (Self::derive_key(config, config.seed_offset, config.init_offset), config.intermediate_xor)
+                    }
+                    KeySchedule::Seeded => {
+                        let base = Self::derive_key(config, config.seed_base, config.init_base);
+                        let offset = Self::derive_key(config, config.seed_offset, config.init_offset);
+                        (
+                            Self::derive_key(config, config.seed_offset ^ base, config.init_offset),
+                            Self::derive_key(config, config.seed_base ^ offset, config.intermediate_xor),
+                        )
+                    }
+                };
+
+                let feistel_key = match config.backend {
+                    Backend::Feistel { .. } => feistel::key(
+                        (config.seed_base ^ config.init_base) as u128,
+                        (config.seed_offset ^ config.init_offset) as u128,
+                    ),
+                    _ => 0,
+                };
+
+                Keys {
+                    start_index,
+                    intermediate_offset,
+                    intermediate_xor,
+                    tail_window: Self::tail_window(config),
+                    feistel_key,
+                }
+            }
+
+            /// See [RandomSequenceBuilder::permute].
+            #[inline(always)]
+            pub(crate) const fn permute(config: &RandomSequenceBuilder<$type>, index: $type, keys: &Keys<$type>) -> $type {
+                match config.backend {
+                    Backend::Feistel { rounds } => {
+                        let bits = Self::mask(config).count_ones();
+                        let mut value = index;
+                        loop {
+                            value = feistel::permute(value as u128, bits, keys.feistel_key, rounds) as $type;
+                            if Self::in_domain(config, value) {
+                                return value;
+                            }
+                        }
+                    }
+                    _ => Self::permute_rounds(config, Self::permute_pass(index, config.prime, keys.tail_window), keys),
+                }
+            }
+
+            /// See [RandomSequenceBuilder::permute_rounds].
+            #[inline(always)]
+            pub(crate) const fn permute_rounds(config: &RandomSequenceBuilder<$type>, first: $type, keys: &Keys<$type>) -> $type {
+                let mask = Self::mask(config);
+                let mut value = first;
+                loop {
+                    let (mut offset, mut xor) = (keys.intermediate_offset, keys.intermediate_xor);
+                    let mut round = 1;
+                    while round < config.rounds {
+                        let x = (value.wrapping_add(offset) & mask) ^ (xor & mask);
+                        value = Self::permute_pass(x, config.prime, keys.tail_window);
+                        (offset, xor) = Self::next_round_key(offset, xor, keys.intermediate_offset);
+                        round += 1;
+                    }
+                    if Self::in_domain(config, value) {
+                        return value;
+                    }
+                    value = Self::permute_pass(value, config.prime, keys.tail_window);
+                }
+            }
+
+            /// See the `Permutation::apply` of [BuilderPermutation].
+            #[inline(always)]
+            pub(crate) const fn apply(permutation: &BuilderPermutation<$type>, index: $type) -> $type {
+                let (config, keys) = (&permutation.config, &permutation.keys);
+                let index = Self::add_index(config, keys.start_index, index);
+                Self::map_range(config, Self::permute(config, index, keys))
+            }
+        }
+    };
+}
+
+impl_const!(u8);
+impl_const!(u16);
+impl_const!(u32);
+impl_const!(u64);
+impl_const!(usize);
+impl_const!(u128);
+
+macro_rules! impl_const_sequence {
+    ($type:ident, $unsigned:ident) => {
+        impl RandomSequence<$type> {
+            /// Initialise a random sequence from the seeds in a `const` context, the same sequence
+            /// as [RandomSequence::new].
+            ///
+            /// ```rust
+            /// use rand_unique::RandomSequence;
+            ///
+            #[doc = concat!("const SEQUENCE: RandomSequence<", stringify!($type), "> = RandomSequence::<", stringify!($type), ">::const_new(7, 11);")]
+            #[doc = concat!("static TABLE: [", stringify!($type), "; 4] = [SEQUENCE.const_n(0), SEQUENCE.const_n(1), SEQUENCE.const_n(2), SEQUENCE.const_n(3)];")]
+            ///
+            #[doc = concat!("let sequence = RandomSequence::<", stringify!($type), ">::new(7, 11);")]
+            /// assert_eq!(TABLE, [sequence.n(0), sequence.n(1), sequence.n(2), sequence.n(3)]);
+            /// ```
+            pub const fn const_new(seed_base: $type, seed_offset: $type) -> Self {
+                let config = RandomSequenceBuilder::<$unsigned>::new(seed_base as $unsigned, seed_offset as $unsigned);
+                RandomSequence {
+                    permutation: BuilderPermutation {
+                        keys: Const::<$unsigned>::keys(&config),
+                        config,
+                        square: None,
+                        #[cfg(target_arch = "x86_64")]
+                        lanes: DerivedLanes::Deferred,
+                    },
+                    current_index: 0,
+                    ended: false,
+                }
+            }
+
+            /// Get the nth element in the sequence in a `const` context, the same value as
+            /// [RandomSequence::n], for any sequence of the default [BuilderPermutation].
+            pub const fn const_n(&self, index: $type) -> $type {
+                let permutation = &self.permutation;
+                let index = Const::<$unsigned>::add_index(&permutation.config, 0, index as $unsigned);
+                Const::<$unsigned>::apply(permutation, index) as $type
+            }
+        }
+    };
+}
+
+impl_const_sequence!(u8, u8);
+impl_const_sequence!(u16, u16);
+impl_const_sequence!(u32, u32);
+impl_const_sequence!(u64, u64);
+impl_const_sequence!(usize, usize);
+impl_const_sequence!(u128, u128);
+impl_const_sequence!(i8, u8);
+impl_const_sequence!(i16, u16);
+impl_const_sequence!(i32, u32);
+impl_const_sequence!(i64, u64);
+impl_const_sequence!(isize, usize);
+impl_const_sequence!(i128, u128);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::test_configs;
+
+    macro_rules! test_const {
+        ($name:ident, $type:ident, $unsigned:ident) => {
+            #[test]
+            fn $name() {
+                const SEQUENCE: RandomSequence<$type> = RandomSequence::<$type>::const_new(7, 11);
+                const FIRST: $type = SEQUENCE.const_n(0);
+                const LAST: $type = SEQUENCE.const_n((0 as $type).wrapping_sub(1));
+
+                let sequence = RandomSequence::<$type>::new(7, 11);
+                assert_eq!(FIRST, sequence.n(0));
+                assert_eq!(LAST, sequence.n((0 as $type).wrapping_sub(1)));
+                assert_eq!(SEQUENCE.permutation.config, sequence.permutation.config);
+                assert_eq!(SEQUENCE.permutation.keys, sequence.permutation.keys);
+
+                // const_n matches n for every configuration of the builder
+                for config in test_configs::<$unsigned>() {
+                    let sequence = RandomSequence::<$type>::from(config);
+                    for index in (0..200).map(|i| i as $type).chain([$type::MAX, $type::MIN, $type::MAX / 3]) {
+                        assert_eq!(sequence.const_n(index), sequence.n(index), "{:?} at {}", config, index);
+                    }
+                }
+            }
+        };
+    }
+
+    test_const!(test_u8_const, u8, u8);
+    test_const!(test_u16_const, u16, u16);
+    test_const!(test_u32_const, u32, u32);
+    test_const!(test_u64_const, u64, u64);
+    test_const!(test_usize_const, usize, usize);
+    test_const!(test_u128_const, u128, u128);
+    test_const!(test_i8_const, i8, u8);
+    test_const!(test_i32_const, i32, u32);
+    test_const!(test_i128_const, i128, u128);
+}
@@ -0,0 +1,54 @@
+use core::fmt;
+
+/// An error from validating the parameters of a [RandomSequenceBuilder](crate::RandomSequenceBuilder),
+/// see [RandomSequenceBuilder::validate](crate::RandomSequenceBuilder::validate).
+///
+/// Each of these would otherwise generate a sequence with duplicate values, or panic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The `prime` isn't a prime number.
+    NotPrime,
+
+    /// The `prime` doesn't satisfy `prime = 3 mod 4`.
+    NotThreeModFour,
+
+    /// The `prime` is above the power of two range `0..2^k` that the permutation operates over,
+    /// so its residues could leave the range.
+    PrimeOutOfRange,
+
+    /// The `prime` is so far below the top of the permutation range that no tail window of
+    /// `fold_tail` can hold the values above it.
+    PrimeTooSmall,
+
+    /// The `domain` is zero, so the sequence would have no values.
+    EmptyDomain,
+
+    /// The `range_step` is zero, or the range `range_start + range_step * (domain - 1)` overflows
+    /// the type, as both an unsigned and a signed range.
+    InvalidRange,
+
+    /// The number of `rounds`, or of the rounds of the `Backend::Feistel` backend, is zero.
+    ZeroRounds,
+
+    /// The `backend` permutes values as a u128, and so can't permute a type wider than 128 bits.
+    BackendUnsupported,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Error::NotPrime => "The prime is not a prime number.",
+            Error::NotThreeModFour => "The prime doesn't satisfy prime = 3 mod 4.",
+            Error::PrimeOutOfRange => "The prime is larger than the permutation range.",
+            Error::PrimeTooSmall => "The prime is too small to fold the tail of the permutation range.",
+            Error::EmptyDomain => "The domain must contain at least one value.",
+            Error::InvalidRange => "The range step must be non-zero, and the range must fit in the type.",
+            Error::ZeroRounds => "The permutation requires at least one round.",
+            Error::BackendUnsupported => "The backend only supports types up to 128 bits.",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
@@ -0,0 +1,123 @@
+/// Permute a `bits`-wide value with a keyed Feistel network.
+///
+/// The value is split into a high `left` half of `bits - bits / 2` bits, and a low `right` half of
+/// `bits / 2` bits. The network is balanced when `bits` is even, and unbalanced otherwise. Rounds
+/// alternate between xor-ing the left half with the round function of the right half, and vice
+/// versa, so each round is its own inverse regardless of the round function.
+#[inline]
+pub(crate) const fn permute(value: u128, bits: u32, key: u64, rounds: u8) -> u128 {
+    let (mut left, mut right, left_mask, right_mask, right_bits) = split(value, bits);
+    let mut round = 0;
+    while round < rounds {
+        if round % 2 == 0 {
+            left ^= round_function(right, round, key) & left_mask;
+        } else {
+            right ^= round_function(left, round, key) & right_mask;
+        }
+        round += 1;
+    }
+    ((left as u128) << right_bits) | right as u128
+}
+
+/// Inverse of [permute], applying the rounds in reverse order.
+#[inline]
+pub(crate) fn invert(value: u128, bits: u32, key: u64, rounds: u8) -> u128 {
+    let (mut left, mut right, left_mask, right_mask, right_bits) = split(value, bits);
+    for round in (0..rounds).rev() {
+        if round % 2 == 0 {
+            left ^= round_function(right, round, key) & left_mask;
+        } else {
+            right ^= round_function(left, round, key) & right_mask;
+        }
+    }
+    ((left as u128) << right_bits) | right as u128
+}
+
+/// Derive the 64-bit Feistel key from a pair of seeds.
+///
+/// Each 64-bit half of the seeds is mixed in turn, so every bit of both seeds affects the key and
+/// no two halves can cancel each other out. The key is still only 64 bits, so two seed pairs of
+/// `u128` share a key with a probability of `2^-64`.
+#[inline]
+pub(crate) const fn key(seed_base: u128, seed_offset: u128) -> u64 {
+    let key = mix(mix(seed_offset as u64) ^ (seed_offset >> 64) as u64);
+    mix(mix(key ^ seed_base as u64) ^ (seed_base >> 64) as u64)
+}
+
+/// Split a value into its `(left, right, left_mask, right_mask, right_bits)` halves.
+#[inline(always)]
+const fn split(value: u128, bits: u32) -> (u64, u64, u64, u64, u32) {
+    debug_assert!(bits >= 2 && bits <= 128, "Feistel networks require 2 to 128 bits.");
+    let right_bits = bits / 2;
+    let left_bits = bits - right_bits;
+    let right_mask = u64::MAX >> (64 - right_bits);
+    let left_mask = u64::MAX >> (64 - left_bits);
+    let left = (value >> right_bits) as u64 & left_mask;
+    let right = value as u64 & right_mask;
+    (left, right, left_mask, right_mask, right_bits)
+}
+
+/// The keyed round function, a distinct key per round mixed into one half of the value.
+#[inline(always)]
+const fn round_function(half: u64, round: u8, key: u64) -> u64 {
+    let round_key = key.wrapping_add((round as u64 + 1).wrapping_mul(0x9e3779b97f4a7c15));
+    mix(half ^ round_key)
+}
+
+/// The splitmix64 finaliser, a bijective mixing function with good avalanche properties.
+#[inline(always)]
+pub(crate) const fn mix(x: u64) -> u64 {
+    let x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_feistel_permutation() {
+        let key = key(0x682f01615bf03635, 0x46790905682f0161);
+
+        // check small balanced and unbalanced widths exhaustively
+        for bits in 2..=16 {
+            for rounds in [0, 1, 3, 8] {
+                let outputs: HashSet<u128> = (0..1u128 << bits)
+                    .map(|x| {
+                        let y = permute(x, bits, key, rounds);
+                        assert_eq!(invert(y, bits, key, rounds), x);
+                        y
+                    })
+                    .collect();
+                assert_eq!(outputs.len(), 1 << bits, "Not a permutation for {} bits", bits);
+                assert!(outputs.iter().all(|y| *y < 1 << bits), "Output out of range for {} bits", bits);
+            }
+        }
+
+        // check wider values invert and stay in range
+        for bits in [31, 32, 33, 63, 64, 65, 127, 128] {
+            let mask = u128::MAX >> (128 - bits);
+            for x in (0..1000u128).chain([mask - 1, mask, mask / 3]) {
+                let y = permute(x, bits, key, 8);
+                assert!(y <= mask);
+                assert_eq!(invert(y, bits, key, 8), x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_feistel_key() {
+        assert_ne!(key(0, 0), key(1, 0));
+        assert_ne!(key(0, 0), key(0, 1));
+        assert_ne!(key(1, 0), key(0, 1));
+
+        // the halves of a seed, or of both seeds, differing by the same bits don't cancel
+        let delta = 0x5bf0363546790905;
+        let pairs = [(0, 0), (delta | delta << 64, 0), (0, delta | delta << 64), (delta, delta), (delta << 64, delta << 64)];
+        let keys: HashSet<u64> = pairs.iter().map(|(base, offset)| key(*base, *offset)).collect();
+        assert_eq!(keys.len(), pairs.len());
+    }
+}
@@ -0,0 +1,603 @@
+use core::ops::RangeInclusive;
+
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::{Aes128Enc, Aes256Enc};
+
+use crate::builder::{from_u128, to_u128, QuadraticResidue, RandomSequenceBuilder, RandomSequenceBuilderInit};
+use crate::permutation::Permutation;
+use crate::sequence::RandomSequence;
+use crate::signed::SequenceInt;
+
+/// An AES key for the FF1 format-preserving encryption of an [Ff1Sequence].
+///
+/// The key replaces `seed_base` and `seed_offset` as the secret defining the sequence. It should
+/// be generated by a cryptographically secure random number generator and kept secret.
+///
+/// Keys aren't serializable, so they can't be written out alongside a non-secret config by
+/// accident, and aren't comparable, as a comparison of the bytes wouldn't run in constant time.
+/// Read the key bytes from the variant to store them in a secret store.
+///
+/// Keys aren't `Copy`, so the key material is only duplicated by an explicit `clone`. With the
+/// `zeroize` feature, keys and the AES key schedules expanded from them are wiped when dropped,
+/// otherwise the memory they occupied isn't cleared.
+#[derive(Clone)]
+pub enum Ff1Key {
+    /// A 128-bit AES-128 key.
+    Aes128([u8; 16]),
+
+    /// A 256-bit AES-256 key.
+    Aes256([u8; 32]),
+}
+
+impl core::fmt::Debug for Ff1Key {
+    /// Redacts the key material, so keys aren't leaked through debug logging of a builder.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Ff1Key::Aes128(_) => f.write_str("Aes128(..)"),
+            Ff1Key::Aes256(_) => f.write_str("Aes256(..)"),
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Ff1Key {
+    /// Wipes the key material.
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+
+        match self {
+            Ff1Key::Aes128(key) => key.zeroize(),
+            Ff1Key::Aes256(key) => key.zeroize(),
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for Ff1Key {}
+
+/// The configuration of an [Ff1Sequence], FF1 format-preserving encryption (NIST SP 800-38G) over
+/// the bits of the sequence domain, keyed by a 128-bit or 256-bit AES key rather than the seeds of
+/// a [RandomSequenceBuilder].
+///
+/// The sequence is the encryption of each index, `n(i) = FF1(key, i)`, without a seeded start
+/// index. Unlike the [Backend](crate::Backend) permutations of a [RandomSequenceBuilder], the
+/// sequence is cryptographically strong, and can't be predicted from its outputs without the key.
+/// Each value costs 11 AES block encryptions, or more when cycle-walking a domain.
+///
+/// NIST requires at least 2^20 values in the domain, smaller domains are permuted but don't meet
+/// the FF1 security bounds.
+///
+/// The builder holds the key, so unlike a [RandomSequenceBuilder] it isn't serializable.
+///
+/// ```rust
+/// use rand_unique::{Ff1Builder, Ff1Key, Ff1Sequence};
+///
+/// let key = Ff1Key::Aes128([0x2b; 16]);
+/// let sequence: Ff1Sequence<u32> = Ff1Builder::<u32>::new(key).with_domain(1_000_000).into_iter();
+/// let value = sequence.n(0);
+/// assert!(value < 1_000_000);
+/// assert_eq!(sequence.index_of(value), 0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Ff1Builder<T>
+where
+    T: QuadraticResidue
+{
+    /// The domain and range the sequence is mapped onto, whose seeds and permutation parameters
+    /// are unused.
+    range: RandomSequenceBuilder<T>,
+
+    key: Ff1Key,
+}
+
+impl<T> Ff1Builder<T>
+where
+    T: QuadraticResidue,
+    RandomSequenceBuilder<T>: RandomSequenceBuilderInit<T>,
+{
+    /// Initialise an FF1 builder over the full range of `T` from an AES key.
+    pub fn new(key: Ff1Key) -> Self {
+        Self {
+            range: RandomSequenceBuilder::<T>::new(T::zero(), T::zero()),
+            key,
+        }
+    }
+}
+
+impl<T> Ff1Builder<T>
+where
+    T: QuadraticResidue
+{
+    /// Restrict the sequence to the values `0..domain`, cycle-walking the encryption of the
+    /// smallest power of two range covering the domain, see [RandomSequenceBuilder::with_domain].
+    ///
+    /// # Panics
+    ///
+    /// If `domain` is zero.
+    pub fn with_domain(self, domain: T) -> Self {
+        Self { range: self.range.with_domain(domain), ..self }
+    }
+
+    /// Restrict the sequence to the `bits`-bit values `0..2^bits`, see
+    /// [RandomSequenceBuilder::with_bits].
+    ///
+    /// # Panics
+    ///
+    /// If `bits` is zero or wider than `T`.
+    pub fn with_bits(self, bits: u32) -> Self {
+        Self { range: self.range.with_bits(bits), ..self }
+    }
+
+    /// Map the sequence onto the values in `range`, see [RandomSequenceBuilder::with_range].
+    ///
+    /// # Panics
+    ///
+    /// If `range` is empty.
+    pub fn with_range(self, range: RangeInclusive<T>) -> Self {
+        Self { range: self.range.with_range(range), ..self }
+    }
+
+    /// Map the sequence onto every `step`-th value in `range`, see
+    /// [RandomSequenceBuilder::with_range_step].
+    ///
+    /// # Panics
+    ///
+    /// If `range` is empty, or `step` is zero.
+    pub fn with_range_step(self, range: RangeInclusive<T>, step: T) -> Self {
+        Self { range: self.range.with_range_step(range, step), ..self }
+    }
+
+    /// The number of values in the sequence, or `None` for the entire range of `T`.
+    #[inline]
+    pub fn domain(&self) -> Option<T> {
+        self.range.domain
+    }
+
+    /// The AES key of the sequence.
+    #[inline]
+    pub fn key(&self) -> &Ff1Key {
+        &self.key
+    }
+}
+
+impl<T> IntoIterator for Ff1Builder<T>
+where
+    T: QuadraticResidue + SequenceInt<Unsigned = T>,
+    Ff1Sequence<T>: Iterator<Item = T>,
+{
+    type Item = T;
+    type IntoIter = Ff1Sequence<T>;
+
+    /// Build an [Ff1Sequence] iterator from this config, expanding the AES key.
+    fn into_iter(self) -> Self::IntoIter {
+        Ff1Sequence::from(self)
+    }
+}
+
+/// A cryptographically strong sequence of unique numbers, the FF1 permutation of an [Ff1Builder]
+/// with its AES key expanded once for the whole sequence.
+///
+/// ```rust
+/// use rand_unique::{Ff1Key, Ff1Sequence};
+///
+/// let key = Ff1Key::Aes128([0x2b; 16]);
+/// let sequence = Ff1Sequence::<u32>::new(key);
+/// let value = sequence.n(0);
+/// assert_eq!(sequence.index_of(value), 0);
+/// ```
+pub type Ff1Sequence<T> = RandomSequence<T, Ff1Permutation<<T as SequenceInt>::Unsigned>>;
+
+/// The [Permutation] of an [Ff1Sequence], the FF1 permutation of an [Ff1Builder] with its expanded
+/// AES key.
+#[derive(Debug, Clone)]
+pub struct Ff1Permutation<U>
+where
+    U: QuadraticResidue
+{
+    config: Ff1Builder<U>,
+    cipher: Cipher,
+}
+
+impl<U> Ff1Permutation<U>
+where
+    U: QuadraticResidue
+{
+    /// The config/builder that holds the key and range that define the sequence.
+    #[inline]
+    pub fn config(&self) -> &Ff1Builder<U> {
+        &self.config
+    }
+
+    /// Encrypt an index, cycle-walking values outside of the domain.
+    #[inline]
+    fn permute(&self, index: U) -> U {
+        let range = &self.config.range;
+        let bits = range.mask().count_ones();
+        let mut value = index;
+        loop {
+            value = from_u128(permute(to_u128(value), bits, &self.cipher));
+            if range.in_domain(value) {
+                return value;
+            }
+        }
+    }
+
+    /// Inverse of [Ff1Permutation::permute].
+    #[inline]
+    fn invert(&self, value: U) -> U {
+        let range = &self.config.range;
+        let bits = range.mask().count_ones();
+        let mut index = value;
+        loop {
+            index = from_u128(invert(to_u128(index), bits, &self.cipher));
+            if range.in_domain(index) {
+                return index;
+            }
+        }
+    }
+}
+
+impl<T> Permutation<T> for Ff1Permutation<T::Unsigned>
+where
+    T: SequenceInt
+{
+    #[inline]
+    fn max_index(&self) -> T::Unsigned {
+        self.config.range.max_index()
+    }
+
+    /// The sequence starts from index 0, so the index is encrypted directly.
+    #[inline]
+    fn apply(&self, index: T::Unsigned) -> T {
+        T::from_unsigned(self.config.range.map_range(self.permute(index)))
+    }
+
+    #[inline]
+    fn invert(&self, value: T) -> Option<T::Unsigned> {
+        let value = self.config.range.unmap_range(value.to_unsigned())?;
+        Some(Ff1Permutation::invert(self, value))
+    }
+
+    #[inline]
+    fn contains(&self, value: T) -> bool {
+        self.config.range.unmap_range(value.to_unsigned()).is_some()
+    }
+}
+
+impl<T> RandomSequence<T, Ff1Permutation<T::Unsigned>>
+where
+    T: SequenceInt,
+    RandomSequenceBuilder<T::Unsigned>: RandomSequenceBuilderInit<T::Unsigned>,
+{
+    /// Initialise an FF1 sequence over the full range of `T` from an AES key.
+    pub fn new(key: Ff1Key) -> Self {
+        Ff1Builder::<T::Unsigned>::new(key).into()
+    }
+}
+
+impl<T> From<Ff1Builder<T::Unsigned>> for RandomSequence<T, Ff1Permutation<T::Unsigned>>
+where
+    T: SequenceInt
+{
+    /// Generate the sequence of an FF1 builder, expanding its AES key.
+    fn from(config: Ff1Builder<T::Unsigned>) -> Self {
+        RandomSequence::from_permutation(Ff1Permutation {
+            cipher: Cipher::new(&config.key),
+            config,
+        })
+    }
+}
+
+/// An expanded AES encryption key schedule, as FF1 only encrypts.
+///
+/// Boxing the larger AES-256 key schedule would require `alloc`.
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+enum Cipher {
+    Aes128(Aes128Enc),
+    Aes256(Aes256Enc),
+}
+
+impl Cipher {
+    #[inline]
+    fn new(key: &Ff1Key) -> Self {
+        match key {
+            Ff1Key::Aes128(key) => Cipher::Aes128(Aes128Enc::new(key.into())),
+            Ff1Key::Aes256(key) => Cipher::Aes256(Aes256Enc::new(key.into())),
+        }
+    }
+
+    #[inline(always)]
+    fn encrypt(&self, block: &mut [u8; 16]) {
+        match self {
+            Cipher::Aes128(cipher) => cipher.encrypt_block(block.into()),
+            Cipher::Aes256(cipher) => cipher.encrypt_block(block.into()),
+        }
+    }
+}
+
+/// Encrypt a `bits`-wide value with FF1 over radix 2 and an empty tweak.
+///
+/// The value is read as a string of `bits` binary numerals, most significant first, so the FF1
+/// halves `A` and `B` are the high `bits / 2` bits and the low `bits - bits / 2` bits.
+#[inline]
+fn permute(value: u128, bits: u32, cipher: &Cipher) -> u128 {
+    let (u, v) = (bits / 2, bits - bits / 2);
+    let (a, b) = encrypt(cipher, 2, u, v, value >> v, value & mask(v), &[]);
+    (a << v) | b
+}
+
+/// Inverse of [permute], decrypting a `bits`-wide value.
+#[inline]
+fn invert(value: u128, bits: u32, cipher: &Cipher) -> u128 {
+    let (u, v) = (bits / 2, bits - bits / 2);
+    let (a, b) = decrypt(cipher, 2, u, v, value >> v, value & mask(v), &[]);
+    (a << v) | b
+}
+
+#[inline(always)]
+fn mask(bits: u32) -> u128 {
+    u128::MAX >> (128 - bits)
+}
+
+/// FF1 encryption, NIST SP 800-38G algorithm 7, of the numeral string `A || B`.
+///
+/// The string has `u + v` numerals in `radix`, where `A` is the first `u = n / 2` numerals with
+/// value `a`, and `B` is the remaining `v` numerals with value `b`. Only the numeric values of the
+/// halves are needed between rounds, so strings are never materialised. Halves are limited to
+/// `radix^v <= 2^96`, which keeps every intermediate value within a `u128`.
+fn encrypt(cipher: &Cipher, radix: u32, u: u32, v: u32, mut a: u128, mut b: u128, tweak: &[u8]) -> (u128, u128) {
+    let params = Params::new(cipher, radix, u, v, tweak);
+    for round in 0..10 {
+        let modulus = if round % 2 == 0 { params.modulus_u } else { params.modulus_v };
+        let y = params.round_function(cipher, round, b, tweak) % modulus;
+        let c = (a + y) % modulus;
+        a = b;
+        b = c;
+    }
+    (a, b)
+}
+
+/// FF1 decryption, NIST SP 800-38G algorithm 8, the inverse of [encrypt].
+fn decrypt(cipher: &Cipher, radix: u32, u: u32, v: u32, mut a: u128, mut b: u128, tweak: &[u8]) -> (u128, u128) {
+    let params = Params::new(cipher, radix, u, v, tweak);
+    for round in (0..10).rev() {
+        let modulus = if round % 2 == 0 { params.modulus_u } else { params.modulus_v };
+        let y = params.round_function(cipher, round, a, tweak) % modulus;
+        let c = (b + modulus - y) % modulus;
+        b = a;
+        a = c;
+    }
+    (a, b)
+}
+
+/// The per-message constants of FF1.
+struct Params {
+    /// `CIPH(P)` of the fixed first block `P`, the CBC-MAC state every round starts from.
+    p: [u8; 16],
+    /// `radix^u`, the modulus of even rounds.
+    modulus_u: u128,
+    /// `radix^v`, the modulus of odd rounds.
+    modulus_v: u128,
+    /// The number of bytes `b` used to encode `NUM(B)`.
+    b: usize,
+}
+
+impl Params {
+    #[inline(always)]
+    fn new(cipher: &Cipher, radix: u32, u: u32, v: u32, tweak: &[u8]) -> Self {
+        debug_assert!((2..=1 << 16).contains(&radix), "FF1 requires a radix of 2 to 2^16.");
+        debug_assert!(u <= v && v <= u + 1, "FF1 requires u = floor(n / 2).");
+        assert!(tweak.len() <= 32, "The tweak must be at most 32 bytes.");
+        let modulus_u = (radix as u128).pow(u);
+        let modulus_v = (radix as u128).checked_pow(v).filter(|m| *m <= 1 << 96).expect("FF1 halves are limited to 2^96.");
+
+        // Op: `b = ceil(ceil(v * log2(radix)) / 8)`, the byte length of `radix^v - 1`
+        let b = ((128 - (modulus_v - 1).leading_zeros()) as usize).div_ceil(8);
+
+        let n = u + v;
+        let t = tweak.len() as u32;
+        let mut p = [1, 2, 1, 0, 0, 0, 10, u as u8, 0, 0, 0, 0, 0, 0, 0, 0];
+        p[3..6].copy_from_slice(&radix.to_be_bytes()[1..]);
+        p[8..12].copy_from_slice(&n.to_be_bytes());
+        p[12..16].copy_from_slice(&t.to_be_bytes());
+        cipher.encrypt(&mut p);
+
+        Self { p, modulus_u, modulus_v, b }
+    }
+
+    /// `NUM(S)` for `S` the first `d` bytes of `PRF(P || Q)`, the CBC-MAC of the round input.
+    ///
+    /// As `b <= 12`, `d = 4 * ceil(b / 4) + 4 <= 16` and `S` is always a prefix of a single block.
+    /// `P` is the same for every round, so the MAC continues from the cached `CIPH(P)`.
+    #[inline(always)]
+    fn round_function(&self, cipher: &Cipher, round: u8, half: u128, tweak: &[u8]) -> u128 {
+        // Op: `Q = T || [0]^((-t-b-1) mod 16) || [i]^1 || [NUM(B)]^b`, padded to whole blocks
+        let mut q = [0u8; 64];
+        let q_len = (tweak.len() + self.b + 1).div_ceil(16) * 16;
+        q[..tweak.len()].copy_from_slice(tweak);
+        q[q_len - self.b - 1] = round;
+        q[q_len - self.b..q_len].copy_from_slice(&half.to_be_bytes()[16 - self.b..]);
+
+        let mut r = self.p;
+        for block in q[..q_len].chunks_exact(16) {
+            r.iter_mut().zip(block).for_each(|(r, q)| *r ^= q);
+            cipher.encrypt(&mut r);
+        }
+
+        let d = 4 * self.b.div_ceil(4) + 4;
+        u128::from_be_bytes(r) >> (8 * (16 - d))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::string::String;
+    use std::vec::Vec;
+
+    use super::*;
+
+    const KEY_128: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    /// Encrypt a string of digits in `radix` with the tweak, then check it decrypts.
+    fn encrypt_str(key: &Ff1Key, radix: u32, tweak: &[u8], plaintext: &str) -> String {
+        let numerals: Vec<u128> = plaintext.chars().map(|c| c.to_digit(radix).unwrap() as u128).collect();
+        let num = |numerals: &[u128]| numerals.iter().fold(0, |acc, x| acc * radix as u128 + x);
+        let (u, v) = (numerals.len() / 2, numerals.len() - numerals.len() / 2);
+        let (a, b) = (num(&numerals[..u]), num(&numerals[u..]));
+
+        let cipher = Cipher::new(key);
+        let (x, y) = encrypt(&cipher, radix, u as u32, v as u32, a, b, tweak);
+        assert_eq!(decrypt(&cipher, radix, u as u32, v as u32, x, y, tweak), (a, b));
+
+        let str = |mut x: u128, len: usize| {
+            let mut digits: Vec<char> = (0..len)
+                .map(|_| {
+                    let digit = char::from_digit((x % radix as u128) as u32, radix).unwrap();
+                    x /= radix as u128;
+                    digit
+                })
+                .collect();
+            digits.reverse();
+            digits.into_iter().collect::<String>()
+        };
+        str(x, u) + &str(y, v)
+    }
+
+    #[test]
+    fn test_nist_vectors() {
+        // NIST SP 800-38G FF1 samples 1 to 3
+        let key = Ff1Key::Aes128(KEY_128);
+        assert_eq!(encrypt_str(&key, 10, &[], "0123456789"), "2433477484");
+        assert_eq!(
+            encrypt_str(&key, 10, &[0x39, 0x38, 0x37, 0x36, 0x35, 0x34, 0x33, 0x32, 0x31, 0x30], "0123456789"),
+            "6124200773",
+        );
+        assert_eq!(
+            encrypt_str(&key, 36, &[0x37, 0x37, 0x37, 0x37, 0x70, 0x71, 0x72, 0x73, 0x37, 0x37, 0x37], "0123456789abcdefghi"),
+            "a9tv40mll9kdu509eum",
+        );
+
+        // NIST SP 800-38G FF1 samples 7 to 9, with AES-256
+        let key = Ff1Key::Aes256([
+            0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c, 0xef, 0x43,
+            0x59, 0xd8, 0xd5, 0x80, 0xaa, 0x4f, 0x7f, 0x03, 0x6d, 0x6f, 0x04, 0xfc, 0x6a, 0x94,
+        ]);
+        assert_eq!(encrypt_str(&key, 10, &[], "0123456789"), "6657667009");
+        assert_eq!(
+            encrypt_str(&key, 10, &[0x39, 0x38, 0x37, 0x36, 0x35, 0x34, 0x33, 0x32, 0x31, 0x30], "0123456789"),
+            "1001623463",
+        );
+        assert_eq!(
+            encrypt_str(&key, 36, &[0x37, 0x37, 0x37, 0x37, 0x70, 0x71, 0x72, 0x73, 0x37, 0x37, 0x37], "0123456789abcdefghi"),
+            "xs8a0azh2avyalyzuwd",
+        );
+
+        // FIPS-197 appendix C.3, the AES-256 block cipher used for 256-bit keys
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let mut block: [u8; 16] = core::array::from_fn(|i| (i as u8) * 0x11);
+        Cipher::new(&Ff1Key::Aes256(key)).encrypt(&mut block);
+        assert_eq!(u128::from_be_bytes(block), 0x8ea2b7ca516745bfeafc49904b496089);
+    }
+
+    #[test]
+    fn test_ff1_permutation() {
+        let keys = [Ff1Key::Aes128(KEY_128), Ff1Key::Aes256([0x5a; 32])];
+        for key in keys {
+            let cipher = Cipher::new(&key);
+
+            // check small widths exhaustively
+            for bits in 2..=12 {
+                let outputs: HashSet<u128> = (0..1u128 << bits)
+                    .map(|x| {
+                        let y = permute(x, bits, &cipher);
+                        assert_eq!(invert(y, bits, &cipher), x);
+                        y
+                    })
+                    .collect();
+                assert_eq!(outputs.len(), 1 << bits, "Not a permutation for {} bits", bits);
+                assert!(outputs.iter().all(|y| *y < 1 << bits), "Output out of range for {} bits", bits);
+            }
+
+            // check wider values invert and stay in range
+            for bits in [31, 32, 33, 63, 64, 65, 127, 128] {
+                for x in (0..100u128).chain([mask(bits) - 1, mask(bits), mask(bits) / 3]) {
+                    let y = permute(x, bits, &cipher);
+                    assert!(y <= mask(bits));
+                    assert_eq!(invert(y, bits, &cipher), x);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ff1_params() {
+        // the cached CIPH(P) is the encryption of P, and each round continues the CBC-MAC of
+        // P || Q from it with the single block of Q
+        let cipher = Cipher::new(&Ff1Key::Aes256([0x5a; 32]));
+        for bits in [2, 8, 32, 64, 128] {
+            let (u, v) = (bits / 2, bits - bits / 2);
+            let params = Params::new(&cipher, 2, u, v, &[]);
+            let p = [1, 2, 1, 0, 0, 2, 10, u as u8, 0, 0, 0, bits as u8, 0, 0, 0, 0];
+            let mut expected = p;
+            cipher.encrypt(&mut expected);
+            assert_eq!(params.p, expected, "CIPH(P) of {} bits", bits);
+
+            let b = (v as usize).div_ceil(8);
+            let d = 4 * b.div_ceil(4) + 4;
+            for (round, half) in [(0, 0), (3, 1), (9, mask(v))] {
+                let mut q = [0u8; 16];
+                q[15 - b] = round;
+                q[16 - b..].copy_from_slice(&half.to_be_bytes()[16 - b..]);
+                let mut r = [0u8; 16];
+                for block in [p, q] {
+                    r.iter_mut().zip(block).for_each(|(r, x)| *r ^= x);
+                    cipher.encrypt(&mut r);
+                }
+                let expected = u128::from_be_bytes(r) >> (8 * (16 - d));
+                assert_eq!(params.round_function(&cipher, round, half, &[]), expected, "Round {} of {} bits", round, bits);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ff1_keys() {
+        let a = Cipher::new(&Ff1Key::Aes128(KEY_128));
+        let b = Cipher::new(&Ff1Key::Aes128([0; 16]));
+        let c = Cipher::new(&Ff1Key::Aes256([0; 32]));
+        let outputs = |cipher: &Cipher| (0..16).map(|x| permute(x, 32, cipher)).collect::<Vec<_>>();
+        assert_ne!(outputs(&a), outputs(&b));
+        assert_ne!(outputs(&b), outputs(&c));
+    }
+
+    #[test]
+    fn test_ff1_sequence() {
+        // the sequence is the cycle-walked encryption of each index, mapped onto the range
+        let key = Ff1Key::Aes256([0x5a; 32]);
+        let config = Ff1Builder::<u32>::new(key.clone()).with_range_step(100..=10_000, 3);
+        assert_eq!(config.domain(), Some(3301));
+        let sequence = Ff1Sequence::<u32>::from(config);
+        let cipher = Cipher::new(&key);
+        assert_eq!(sequence.clone().count(), 3301);
+        for (index, value) in sequence.clone().enumerate() {
+            let mut expected = permute(index as u128, 12, &cipher);
+            while expected >= 3301 {
+                expected = permute(expected, 12, &cipher);
+            }
+            assert_eq!(value, 100 + 3 * expected as u32);
+            assert_eq!(sequence.index_of(value), index as u32);
+        }
+        for value in [99, 100, 101, 103, 10_000, 10_003] {
+            assert_eq!(sequence.contains(value), (100..=10_000).contains(&value) && value % 3 == 1);
+        }
+
+        // signed sequences share the permutation of the unsigned type
+        let sequence = Ff1Sequence::<i16>::new(key.clone());
+        let expected = Ff1Sequence::<u16>::new(key);
+        assert!((0..1000).all(|i| sequence.n(i as i16) == expected.n(i) as i16));
+        assert_eq!(sequence.index_of(sequence.n(-1)), -1);
+    }
+}
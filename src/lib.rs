@@ -1,16 +1,57 @@
 #![doc = include_str!("../README.md")]
 #![warn(missing_docs)]
 #![no_std]
-#[cfg(test)]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(any(test, feature = "std"))]
 extern crate std;
 
 #[doc(inline)]
-pub use crate::builder::RandomSequenceBuilder;
+pub use crate::bits::{BitWidth, Bits, BitsPermutation, RandomBitsSequence};
+#[doc(inline)]
+pub use crate::builder::{Backend, KeySchedule, QuadraticResidue, RandomSequenceBuilder, RandomSequenceBuilderInit};
+#[doc(inline)]
+pub use crate::error::Error;
+#[cfg(feature = "ff1")]
+#[doc(inline)]
+pub use crate::ff1::{Ff1Builder, Ff1Key, Ff1Permutation, Ff1Sequence};
+#[doc(inline)]
+pub use crate::permutation::{BuilderPermutation, Permutation};
+#[doc(inline)]
+pub use crate::portable::{PortablePermutation, PortableSequence};
+#[cfg(feature = "std")]
+#[doc(inline)]
+pub use crate::search::{ParameterSearch, ParameterSpec};
 #[doc(inline)]
 pub use crate::sequence::RandomSequence;
+#[doc(inline)]
+pub use crate::shard::{Shard, Shards};
+#[doc(inline)]
+pub use crate::signed::SequenceInt;
+#[doc(inline)]
+pub use crate::table::TablePermutation;
+#[doc(inline)]
+pub use crate::window::Window;
 
+mod bits;
 mod builder;
+mod constant;
+mod error;
+mod feistel;
+#[cfg(feature = "ff1")]
+mod ff1;
+mod permutation;
+mod portable;
+mod primes;
 #[cfg(feature = "rand")]
 mod rand;
+#[cfg(feature = "std")]
+mod search;
 mod seed;
 mod sequence;
+mod shard;
+mod signed;
+#[cfg(target_arch = "x86_64")]
+mod simd;
+mod table;
+mod window;
@@ -0,0 +1,206 @@
+use num_traits::Bounded;
+
+use crate::builder::{type_bits, Backend, Keys, QuadraticResidue, RandomSequenceBuilder};
+use crate::signed::SequenceInt;
+#[cfg(target_arch = "x86_64")]
+use crate::simd::Lanes;
+
+/// A bijection from the indices `0..=max_index` onto the values of a [RandomSequence](crate::RandomSequence).
+///
+/// A [RandomSequence](crate::RandomSequence) holds the cursor over the indices, and implements
+/// `next`, `prev`, `wrapping_next`, `set_index`, `index`, and the iterator traits on top of any
+/// permutation. The default is the [BuilderPermutation] defined by a [RandomSequenceBuilder].
+///
+/// Implementations must map each index in `0..=max_index` to a distinct value, otherwise the
+/// sequence is no longer unique. Use [RandomSequence::from_permutation](crate::RandomSequence::from_permutation)
+/// to generate a sequence from a custom permutation.
+pub trait Permutation<T>
+where
+    T: SequenceInt
+{
+    /// The largest index of the permutation, where the sequence has `max_index + 1` values.
+    ///
+    /// Defaults to the entire range of the unsigned index type.
+    #[inline]
+    fn max_index(&self) -> T::Unsigned {
+        T::Unsigned::max_value()
+    }
+
+    /// The value at `index`, for any `index <= max_index`.
+    fn apply(&self, index: T::Unsigned) -> T;
+
+    /// The index of `value`, the inverse of [Permutation::apply], or `None` if `value` isn't an
+    /// output of the permutation.
+    ///
+    /// Defaults to `None` for permutations which can't be inverted, which disables
+    /// [RandomSequence::index_of](crate::RandomSequence::index_of).
+    #[inline]
+    fn invert(&self, value: T) -> Option<T::Unsigned> {
+        let _ = value;
+        None
+    }
+
+    /// Check whether `value` is an output of the permutation.
+    ///
+    /// Defaults to checking whether [Permutation::invert] succeeds, so should be overridden by
+    /// permutations which can't be inverted, or have a cheaper check.
+    #[inline]
+    fn contains(&self, value: T) -> bool {
+        self.invert(value).is_some()
+    }
+
+    /// The value at `index` while iterating, where `index` is usually one after or one before the
+    /// index of the previous call, the same value as [Permutation::apply].
+    ///
+    /// Defaults to [Permutation::apply], so should be overridden by permutations which can reuse
+    /// the work of the previous index, see [RandomSequence::next](crate::RandomSequence::next).
+    #[inline(always)]
+    fn apply_sequential(&mut self, index: T::Unsigned) -> T {
+        self.apply(index)
+    }
+
+    /// Write the value of each of `indices` to `values`, for indices `<= max_index`, the same
+    /// values as [Permutation::apply] for each index.
+    ///
+    /// Defaults to [Permutation::apply] for each index, so should be overridden by permutations
+    /// which can evaluate many indices faster together, see
+    /// [RandomSequence::n_many](crate::RandomSequence::n_many).
+    #[inline]
+    fn apply_many(&self, indices: &[T::Unsigned], values: &mut [T]) {
+        for (index, value) in indices.iter().zip(values) {
+            *value = self.apply(*index);
+        }
+    }
+}
+
+/// The default [Permutation], generating the sequence defined by a [RandomSequenceBuilder].
+///
+/// Caches the `start_index`, round keys and tail window derived from the seeds, so each value costs
+/// a single permutation of the configured [Backend].
+#[derive(Debug, Clone)]
+pub struct BuilderPermutation<U>
+where
+    U: QuadraticResidue
+{
+    /// The config/builder holds the parameters that define the sequence, read only as the keys
+    /// are derived from it.
+    pub(crate) config: RandomSequenceBuilder<U>,
+
+    pub(crate) keys: Keys<U>,
+
+    /// The last absolute index below the prime of a sequential walk, and its square mod prime.
+    pub(crate) square: Option<(U, U)>,
+
+    /// The vectorised lanes of [Permutation::apply_many].
+    #[cfg(target_arch = "x86_64")]
+    pub(crate) lanes: DerivedLanes,
+}
+
+/// The vectorised lanes of a [BuilderPermutation], derived once with its keys.
+#[cfg(target_arch = "x86_64")]
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum DerivedLanes {
+    /// The lanes derived with the keys, or `None` if the permutation can't be vectorised.
+    Derived(Option<Lanes>),
+
+    /// The lanes of a `const_new` sequence, which can't be derived in a `const fn`, so are derived
+    /// for each batch instead.
+    Deferred,
+}
+
+impl<U> BuilderPermutation<U>
+where
+    U: QuadraticResidue
+{
+    /// The config/builder that holds the parameters that define the sequence, which can be
+    /// serialized and restored with `From<RandomSequenceBuilder>`.
+    #[inline]
+    pub fn config(&self) -> &RandomSequenceBuilder<U> {
+        &self.config
+    }
+}
+
+impl<T> Permutation<T> for BuilderPermutation<T::Unsigned>
+where
+    T: SequenceInt
+{
+    #[inline]
+    fn max_index(&self) -> T::Unsigned {
+        self.config.max_index()
+    }
+
+    /// `qpr(qpr(start_index + index + intermediate_offset) ^ intermediate_xor)`, mapped onto the range.
+    #[inline(always)]
+    fn apply(&self, index: T::Unsigned) -> T {
+        let (config, keys) = (&self.config, &self.keys);
+        T::from_unsigned(config.map_range(config.permute(config.add_index(keys.start_index, index), keys)))
+    }
+
+    /// Updates the square of the first residue pass from the previous index with the
+    /// [Backend::QuadraticResidue] backend, rather than squaring each index again. The types of up
+    /// to 16 bits square each index, as their multiply is cheaper than tracking the square.
+    #[inline(always)]
+    fn apply_sequential(&mut self, index: T::Unsigned) -> T {
+        if type_bits::<T::Unsigned>() <= 16 {
+            return self.apply(index);
+        }
+
+        let index = self.config.add_index(self.keys.start_index, index);
+        if !matches!(self.config.backend, Backend::QuadraticResidue) || index >= self.config.prime {
+            self.square = None;
+            return T::from_unsigned(self.config.map_range(self.config.permute(index, &self.keys)));
+        }
+
+        let (first, square) = self.config.permute_pass_step(index, self.square, self.keys.tail_window);
+        self.square = Some((index, square));
+        T::from_unsigned(self.config.map_range(self.config.permute_rounds(first, &self.keys)))
+    }
+
+    #[inline]
+    fn invert(&self, value: T) -> Option<T::Unsigned> {
+        self.config.index_of_internal(&self.keys, value.to_unsigned())
+    }
+
+    #[inline]
+    fn contains(&self, value: T) -> bool {
+        self.config.unmap_range(value.to_unsigned()).is_some()
+    }
+
+    /// Evaluates the residues of many indices together with SSE2 or AVX2 on x86_64, for the
+    /// [Backend::QuadraticResidue](crate::Backend::QuadraticResidue) permutations of up to 64 bits,
+    /// and with AVX2 only above 32 bits.
+    #[inline]
+    fn apply_many(&self, indices: &[T::Unsigned], values: &mut [T]) {
+        #[cfg(target_arch = "x86_64")]
+        {
+            let lanes = match self.lanes {
+                DerivedLanes::Derived(lanes) => lanes,
+                DerivedLanes::Deferred => Lanes::new(&self.config, &self.keys),
+            };
+            if let Some(lanes) = lanes {
+                return lanes.apply(self, indices, values);
+            }
+        }
+
+        for (index, value) in indices.iter().zip(values) {
+            *value = Permutation::<T>::apply(self, *index);
+        }
+    }
+}
+
+impl<U> From<RandomSequenceBuilder<U>> for BuilderPermutation<U>
+where
+    U: QuadraticResidue
+{
+    /// Derive the keys of the builder's permutation.
+    fn from(config: RandomSequenceBuilder<U>) -> Self {
+        let keys = config.keys();
+        Self {
+            config,
+            keys,
+            square: None,
+            #[cfg(target_arch = "x86_64")]
+            lanes: DerivedLanes::Derived(Lanes::new(&config, &keys)),
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use crate::builder::{QuadraticResidue, RandomSequenceBuilder};
+use crate::permutation::{BuilderPermutation, Permutation};
+use crate::sequence::{RandomSequence, BATCH};
+
+/// A deterministic pseudo-random sequence of unique `usize` numbers, defined by the permutation of
+/// an explicit `u32` or `u64` [RandomSequenceBuilder], so the same seeds generate the same
+/// sequence on every target.
+///
+/// A `RandomSequence<usize>` uses the parameters and arithmetic of the `usize` of the target, so
+/// generates the same sequence as a `u64` sequence on 64-bit targets and as a `u32` sequence on
+/// 32-bit targets. A `PortableSequence<u32>` generates the `u32` sequence on every target, and a
+/// `PortableSequence<u64>` generates the `u64` sequence wherever its values fit in a `usize`.
+///
+/// ```rust
+/// use rand_unique::{PortableSequence, RandomSequence};
+///
+/// let sequence = PortableSequence::<u32>::new(10, 2);
+/// let index: usize = sequence.n(0);
+/// assert_eq!(index, RandomSequence::<u32>::new(10, 2).n(0) as usize);
+/// assert_eq!(sequence.index_of(index), 0);
+/// ```
+pub type PortableSequence<U> = RandomSequence<usize, PortablePermutation<U>>;
+
+/// The [Permutation] of a [PortableSequence], the [BuilderPermutation] of a `u32` or `u64`
+/// [RandomSequenceBuilder] with its indices and values converted to `usize`.
+#[derive(Debug, Clone)]
+pub struct PortablePermutation<U>
+where
+    U: QuadraticResidue
+{
+    permutation: BuilderPermutation<U>,
+}
+
+impl<U> PortablePermutation<U>
+where
+    U: QuadraticResidue
+{
+    /// The config/builder of the wrapped permutation, see [BuilderPermutation::config].
+    #[inline]
+    pub fn config(&self) -> &RandomSequenceBuilder<U> {
+        &self.permutation.config
+    }
+}
+
+macro_rules! impl_portable {
+    ($type:ident) => {
+        impl Permutation<usize> for PortablePermutation<$type> {
+            #[inline]
+            fn max_index(&self) -> usize {
+                Permutation::<$type>::max_index(&self.permutation) as usize
+            }
+
+            #[inline(always)]
+            fn apply(&self, index: usize) -> usize {
+                Permutation::<$type>::apply(&self.permutation, index as $type) as usize
+            }
+
+            #[inline(always)]
+            fn apply_sequential(&mut self, index: usize) -> usize {
+                Permutation::<$type>::apply_sequential(&mut self.permutation, index as $type) as usize
+            }
+
+            #[inline]
+            fn invert(&self, value: usize) -> Option<usize> {
+                let value = $type::try_from(value).ok()?;
+                Permutation::<$type>::invert(&self.permutation, value).map(|index| index as usize)
+            }
+
+            #[inline]
+            fn contains(&self, value: usize) -> bool {
+                $type::try_from(value).is_ok_and(|value| Permutation::<$type>::contains(&self.permutation, value))
+            }
+
+            /// Converts the indices and values through buffers of the fixed width type, so the
+            /// batches of the builder's permutation are still vectorised.
+            #[inline]
+            fn apply_many(&self, indices: &[usize], values: &mut [usize]) {
+                let (mut batch, mut permuted) = ([0 as $type; BATCH], [0 as $type; BATCH]);
+                for (indices, values) in indices.chunks(BATCH).zip(values.chunks_mut(BATCH)) {
+                    let (batch, permuted) = (&mut batch[..indices.len()], &mut permuted[..indices.len()]);
+                    for (slot, index) in batch.iter_mut().zip(indices) {
+                        *slot = *index as $type;
+                    }
+                    Permutation::<$type>::apply_many(&self.permutation, batch, permuted);
+                    for (value, permuted) in values.iter_mut().zip(permuted.iter()) {
+                        *value = *permuted as usize;
+                    }
+                }
+            }
+        }
+
+        impl RandomSequence<usize, PortablePermutation<$type>> {
+            /// Initialise a portable random sequence from the seeds of the
+            #[doc = concat!("`", stringify!($type), "`")]
+            /// permutation.
+            pub fn new(seed_base: $type, seed_offset: $type) -> Self {
+                RandomSequenceBuilder::<$type>::new(seed_base, seed_offset).into()
+            }
+        }
+
+        impl From<RandomSequenceBuilder<$type>> for RandomSequence<usize, PortablePermutation<$type>> {
+            /// Generate the sequence of a builder as `usize` values, such as a deserialized
+            /// [PortablePermutation::config].
+            ///
+            /// # Panics
+            ///
+            /// If a value of the sequence doesn't fit in a `usize`, which is only possible for
+            /// `u64` builders on targets with a pointer width below 64 bits.
+            fn from(config: RandomSequenceBuilder<$type>) -> Self {
+                let last = config
+                    .range_step
+                    .checked_mul(config.max_index())
+                    .and_then(|span| span.checked_add(config.range_start));
+                assert!(
+                    last.is_some_and(|last| usize::try_from(last).is_ok()),
+                    "The values of the sequence must fit in a usize.",
+                );
+                RandomSequence::from_permutation(PortablePermutation {
+                    permutation: BuilderPermutation::from(config),
+                })
+            }
+        }
+    };
+}
+
+impl_portable!(u32);
+impl_portable!(u64);
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+
+    macro_rules! test_portable {
+        ($name:ident, $type:ident) => {
+            #[test]
+            fn $name() {
+                // the same sequence as the fixed width type, on every target
+                let sequence = PortableSequence::<$type>::new(7, 11);
+                let fixed = RandomSequence::<$type>::new(7, 11);
+                for index in (0..1000).chain([$type::MAX as usize - 1, $type::MAX as usize]) {
+                    let value = sequence.n(index);
+                    assert_eq!(value, fixed.n(index as $type) as usize);
+                    assert_eq!(sequence.index_of(value), index);
+                    assert!(sequence.contains(value));
+                }
+                let values: Vec<usize> = sequence.clone().take(100).collect();
+                let expected: Vec<usize> = fixed.clone().take(100).map(|value| value as usize).collect();
+                assert_eq!(values, expected);
+                let values: Vec<usize> = sequence.clone().rev().take(100).collect();
+                let expected: Vec<usize> = fixed.clone().rev().take(100).map(|value| value as usize).collect();
+                assert_eq!(values, expected);
+
+                // batches convert through the fixed width type, across several batches
+                let indices: Vec<usize> = (0..200).map(|i| (i as $type).wrapping_mul(97) as usize).collect();
+                let mut values = std::vec![0; indices.len()];
+                sequence.n_many(&indices, &mut values);
+                assert!(indices.iter().zip(&values).all(|(i, value)| fixed.n(*i as $type) as usize == *value));
+                sequence.fill(&mut values, $type::MAX as usize - 50);
+                let expected: Vec<usize> = (0..200).map(|i| fixed.n(($type::MAX - 50).wrapping_add(i)) as usize).collect();
+                assert_eq!(values, expected);
+
+                // the permutation and iterator of a full sequence
+                assert_eq!(sequence.permutation.max_index(), $type::MAX as usize);
+                assert_eq!(*sequence.permutation.config(), RandomSequenceBuilder::<$type>::new(7, 11));
+                let hint = crate::sequence::unsized_hint($type::MAX as u128, usize::MAX as u128);
+                assert_eq!(sequence.size_hint(), hint);
+
+                // domains and ranges map through the builder
+                let config = RandomSequenceBuilder::<$type>::new(7, 11).with_range_step(100..=10_000, 3);
+                let mut sequence = PortableSequence::<$type>::from(config);
+                let mut fixed = config.into_iter();
+                assert_eq!(sequence.size_hint(), (3301, Some(3301)));
+                while let Some(value) = sequence.next() {
+                    assert_eq!(Some(value as $type), fixed.next());
+                }
+                assert_eq!(fixed.next(), None);
+            }
+        };
+    }
+
+    test_portable!(test_u32_portable, u32);
+    #[cfg(target_pointer_width = "64")]
+    test_portable!(test_u64_portable, u64);
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_portable_values() {
+        // values beyond the fixed width type are never in the sequence
+        let sequence = PortableSequence::<u32>::new(7, 11);
+        let outside = u32::MAX as usize + 1;
+        assert!(!sequence.contains(outside));
+        assert_eq!(Permutation::<usize>::invert(&sequence.permutation, outside), None);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_usize_target() {
+        // usize uses the u64 parameters on 64-bit targets, and PortableSequence<u64> reproduces it
+        let usize_sequence = RandomSequence::<usize>::new(7, 11);
+        let sequence = PortableSequence::<u64>::new(7, 11);
+        assert_eq!(RandomSequenceBuilder::<usize>::new(7, 11).prime, RandomSequenceBuilder::<u64>::new(7, 11).prime as usize);
+        for index in (0..1000).chain([usize::MAX - 1, usize::MAX]) {
+            assert_eq!(usize_sequence.n(index), sequence.n(index));
+        }
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_usize_32_bit() {
+        // the parameters a 32-bit target uses for usize are those of u32, so the usize arithmetic of
+        // a 32-bit target generates the PortableSequence<u32>, which matches the u32 sequence
+        let config = RandomSequenceBuilder::<u32>::new(7, 11);
+        let sequence = PortableSequence::<u32>::new(7, 11);
+        let widened = RandomSequenceBuilder::<usize> {
+            seed_base: 7,
+            seed_offset: 11,
+            init_base: config.init_base as usize,
+            init_offset: config.init_offset as usize,
+            prime: config.prime as usize,
+            intermediate_xor: config.intermediate_xor as usize,
+            ..RandomSequenceBuilder::<usize>::new(7, 11)
+        }
+        .with_domain(1 << 32);
+        let widened = RandomSequence::<usize>::from(widened);
+        for index in (0..1000).chain([u32::MAX as usize - 1, u32::MAX as usize]) {
+            assert_eq!(widened.n(index), sequence.n(index));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "must fit in a usize")]
+    fn test_portable_overflow() {
+        // a range which ends beyond u64::MAX is invalid, and checked rather than wrapped into a usize
+        let mut config = RandomSequenceBuilder::<u64>::new(7, 11);
+        config.range_start = 1;
+        assert_eq!(config.validate(), Err(crate::Error::InvalidRange));
+        let _ = PortableSequence::<u64>::from(config);
+    }
+}
@@ -0,0 +1,469 @@
+use crate::builder::{type_bits, QuadraticResidue};
+
+/// The largest prime `p < 2^bits` satisfying `p = 3 mod 4`, for each bit width from 2 to 128.
+///
+/// Indexed by `bits - 2`, see [prime_for_bits].
+const PRIMES_3_MOD_4: [u128; 127] = [
+    3, // 2^2 - 1
+    7, // 2^3 - 1
+    11, // 2^4 - 5
+    31, // 2^5 - 1
+    59, // 2^6 - 5
+    127, // 2^7 - 1
+    251, // 2^8 - 5
+    503, // 2^9 - 9
+    1019, // 2^10 - 5
+    2039, // 2^11 - 9
+    4091, // 2^12 - 5
+    8191, // 2^13 - 1
+    16363, // 2^14 - 21
+    32719, // 2^15 - 49
+    65519, // 2^16 - 17
+    131071, // 2^17 - 1
+    262139, // 2^18 - 5
+    524287, // 2^19 - 1
+    1048571, // 2^20 - 5
+    2097143, // 2^21 - 9
+    4194287, // 2^22 - 17
+    8388587, // 2^23 - 21
+    16777199, // 2^24 - 17
+    33554383, // 2^25 - 49
+    67108859, // 2^26 - 5
+    134217487, // 2^27 - 241
+    268435399, // 2^28 - 57
+    536870879, // 2^29 - 33
+    1073741783, // 2^30 - 41
+    2147483647, // 2^31 - 1
+    4294967291, // 2^32 - 5
+    8589934583, // 2^33 - 9
+    17179869143, // 2^34 - 41
+    34359738319, // 2^35 - 49
+    68719476731, // 2^36 - 5
+    137438953447, // 2^37 - 25
+    274877906899, // 2^38 - 45
+    549755813723, // 2^39 - 165
+    1099511627563, // 2^40 - 213
+    2199023255531, // 2^41 - 21
+    4398046511087, // 2^42 - 17
+    8796093022151, // 2^43 - 57
+    17592186044399, // 2^44 - 17
+    35184372088763, // 2^45 - 69
+    70368744177643, // 2^46 - 21
+    140737488355031, // 2^47 - 297
+    281474976710591, // 2^48 - 65
+    562949953421231, // 2^49 - 81
+    1125899906842511, // 2^50 - 113
+    2251799813685119, // 2^51 - 129
+    4503599627370323, // 2^52 - 173
+    9007199254740847, // 2^53 - 145
+    18014398509481951, // 2^54 - 33
+    36028797018963799, // 2^55 - 169
+    72057594037927931, // 2^56 - 5
+    144115188075855859, // 2^57 - 13
+    288230376151711687, // 2^58 - 57
+    576460752303423263, // 2^59 - 225
+    1152921504606846883, // 2^60 - 93
+    2305843009213693951, // 2^61 - 1
+    4611686018427387847, // 2^62 - 57
+    9223372036854775783, // 2^63 - 25
+    18446744073709551427, // 2^64 - 189
+    36893488147419103183, // 2^65 - 49
+    73786976294838206459, // 2^66 - 5
+    147573952589676412879, // 2^67 - 49
+    295147905179352825731, // 2^68 - 125
+    590295810358705651619, // 2^69 - 93
+    1180591620717411303151, // 2^70 - 273
+    2361183241434822606523, // 2^71 - 325
+    4722366482869645213603, // 2^72 - 93
+    9444732965739290427323, // 2^73 - 69
+    18889465931478580854739, // 2^74 - 45
+    37778931862957161709471, // 2^75 - 97
+    75557863725914323419019, // 2^76 - 117
+    151115727451828646838239, // 2^77 - 33
+    302231454903657293676391, // 2^78 - 153
+    604462909807314587352839, // 2^79 - 249
+    1208925819614629174706111, // 2^80 - 65
+    2417851639229258349412147, // 2^81 - 205
+    4835703278458516698824647, // 2^82 - 57
+    9671406556917033397649311, // 2^83 - 97
+    19342813113834066795298747, // 2^84 - 69
+    38685626227668133590597571, // 2^85 - 61
+    77371252455336267181195223, // 2^86 - 41
+    154742504910672534362390399, // 2^87 - 129
+    309485009821345068724780451, // 2^88 - 605
+    618970019642690137449562111, // 2^89 - 1
+    1237940039285380274899124191, // 2^90 - 33
+    2475880078570760549798248403, // 2^91 - 45
+    4951760157141521099596496747, // 2^92 - 149
+    9903520314283042199192993767, // 2^93 - 25
+    19807040628566084398385987479, // 2^94 - 105
+    39614081257132168796771975131, // 2^95 - 37
+    79228162514264337593543950319, // 2^96 - 17
+    158456325028528675187087900531, // 2^97 - 141
+    316912650057057350374175801279, // 2^98 - 65
+    633825300114114700748351602543, // 2^99 - 145
+    1267650600228229401496703205223, // 2^100 - 153
+    2535301200456458802993406410683, // 2^101 - 69
+    5070602400912917605986812821471, // 2^102 - 33
+    10141204801825835211973625642911, // 2^103 - 97
+    20282409603651670423947251285999, // 2^104 - 17
+    40564819207303340847894502572019, // 2^105 - 13
+    81129638414606681695789005143947, // 2^106 - 117
+    162259276829213363391578010288127, // 2^107 - 1
+    324518553658426726783156020576119, // 2^108 - 137
+    649037107316853453566312041152347, // 2^109 - 165
+    1298074214633706907132624082305003, // 2^110 - 21
+    2596148429267413814265248164610011, // 2^111 - 37
+    5192296858534827628530496329219907, // 2^112 - 189
+    10384593717069655257060992658440059, // 2^113 - 133
+    20769187434139310514121985316880331, // 2^114 - 53
+    41538374868278621028243970633760683, // 2^115 - 85
+    83076749736557242056487941267521467, // 2^116 - 69
+    166153499473114484112975882535042699, // 2^117 - 373
+    332306998946228968225951765070086139, // 2^118 - 5
+    664613997892457936451903530140172219, // 2^119 - 69
+    1329227995784915872903807060280343811, // 2^120 - 765
+    2658455991569831745807614120560689079, // 2^121 - 73
+    5316911983139663491615228241121378191, // 2^122 - 113
+    10633823966279326983230456482242756367, // 2^123 - 241
+    21267647932558653966460912964485513051, // 2^124 - 165
+    42535295865117307932921825928971026423, // 2^125 - 9
+    85070591730234615865843651857942052727, // 2^126 - 137
+    170141183460469231731687303715884105727, // 2^127 - 1
+    340282366920938463463374607431768211283, // 2^128 - 173
+];
+
+/// Get the largest prime `p < 2^bits` satisfying `p = 3 mod 4`.
+///
+/// The smallest such prime is 3, and so any `bits < 2` will also return 3.
+#[inline]
+pub(crate) const fn prime_for_bits(bits: u32) -> u128 {
+    assert!(bits <= 128, "No prime configured above 128 bits.");
+    PRIMES_3_MOD_4[bits.saturating_sub(2) as usize]
+}
+
+/// Get the largest prime `p < 2^bits` satisfying `p = 3 mod 4` as type `T`, from the prime table
+/// up to 128 bits, or by [search_prime] for wider types.
+#[inline]
+pub(crate) fn prime_below<T: QuadraticResidue>(bits: u32) -> T {
+    match bits <= 128 {
+        true => T::from_u128(prime_for_bits(bits)).expect("Prime must fit in the type."),
+        false => search_prime(T::max_value() >> (type_bits::<T>() - bits) as usize),
+    }
+}
+
+/// Search down from the top of the power of two range `0..=mask` for the largest prime satisfying
+/// `prime = 3 mod 4`, where `mask = 2^k - 1 = 3 mod 4` for any `k >= 2`.
+pub(crate) fn search_prime<T: QuadraticResidue>(mask: T) -> T {
+    let four = T::one() << 2;
+    let mut candidate = mask;
+    while !is_prime(candidate) {
+        candidate = candidate - four;
+    }
+    candidate
+}
+
+/// The small primes, used for trial division.
+const SMALL_PRIMES: [u8; 20] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71];
+
+/// Miller-Rabin bases which are deterministic for all `n < 4,759,123,141`.
+const BASES_32: [u64; 3] = [2, 7, 61];
+
+/// Miller-Rabin bases which are deterministic for all `n < 2^64`, found by Jim Sinclair.
+const BASES_64: [u64; 7] = [2, 325, 9375, 28178, 450775, 9780504, 1795265022];
+
+/// Check whether `n` is prime with the Miller-Rabin test, without allocating.
+///
+/// The test is deterministic below `2^64`, using the known sets of bases which have no strong
+/// pseudoprimes in range. Above `2^64` it is the Baillie-PSW test, a Miller-Rabin test to base 2
+/// followed by a [strong_lucas] test. Baillie-PSW has no known counterexample, but isn't proven
+/// to have none, so is a probable prime test for these widths.
+pub(crate) fn is_prime<T: QuadraticResidue>(n: T) -> bool {
+    for p in SMALL_PRIMES {
+        let p = T::from_u8(p).expect("Small primes fit in every type.");
+        if n == p {
+            return true;
+        }
+        if n % p == T::zero() {
+            return false;
+        }
+    }
+    if n < T::one() + T::one() {
+        return false;
+    }
+
+    // types wider than 128 bits are always above 2^64 when they don't fit in a u128
+    let bases: &[u64] = match n.to_u128() {
+        Some(n) if n < 1 << 32 => &BASES_32,
+        Some(n) if n <= u64::MAX as u128 => &BASES_64,
+        _ => &[2],
+    };
+
+    // Op: `n - 1 = d * 2^s` for odd `d`
+    let n_minus_one = n - T::one();
+    let s = n_minus_one.trailing_zeros();
+    let d = n_minus_one >> s as usize;
+
+    'bases: for base in bases {
+        let a = T::from_u64(*base).expect("Bases are below n.") % n;
+        if a == T::zero() {
+            continue;
+        }
+        let mut x = pow_mod(a, d, n);
+        if x == T::one() || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.residue(n);
+            if x == n_minus_one {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    n.to_u64().is_some() || strong_lucas(n)
+}
+
+/// The strong Lucas probable prime test of an odd `n` with no small factors, with the parameters
+/// `P = 1` and `Q = (1 - D) / 4` chosen by Selfridge's method, the first `D` of 5, -7, 9, -11, ...
+/// with the Jacobi symbol `(D/n) = -1`.
+///
+/// With `n + 1 = d * 2^s` for odd `d`, `n` is a strong Lucas probable prime if `U_d = 0` or
+/// `V_(d * 2^r) = 0 mod n` for some `0 <= r < s`.
+fn strong_lucas<T: QuadraticResidue>(n: T) -> bool {
+    // no D exists for a perfect square, which would search forever
+    if is_square(n) {
+        return false;
+    }
+
+    // Op: `D` as `(|D|, negative)`, and `D mod n` and `Q mod n`
+    let (mut d, mut negative) = (5u64, false);
+    loop {
+        let abs = T::from_u64(d).expect("D is far below n.");
+        let d_mod_n = if negative { n - abs } else { abs };
+        match jacobi(d_mod_n, n) {
+            -1 => break,
+            0 => return n == abs,
+            _ => (d, negative) = (d + 2, !negative),
+        }
+    }
+    let abs = T::from_u64(d).expect("D is far below n.");
+    let d_mod_n = if negative { n - abs } else { abs };
+    let four = T::from_u8(4).expect("4 fits in every type.");
+
+    // Op: `Q = (1 - D) / 4`, where `1 - D = 1 + |D|` for negative D, and `-(|D| - 1)` otherwise
+    let q = match negative {
+        true => (abs + T::one()) / four,
+        false => n - (abs - T::one()) / four,
+    };
+
+    // Op: `n + 1 = odd * 2^s` from the trailing ones of `n`, as `n + 1` overflows for the
+    // Mersenne prime `n = T::MAX` of a type with an odd width
+    let s = (!n).trailing_zeros();
+    let odd = match s < type_bits::<T>() {
+        true => (n >> s as usize) + T::one(),
+        false => T::one(),
+    };
+
+    // walk the bits of `odd` from the top, doubling `k` for each bit and adding 1 for set bits
+    let (mut u, mut v, mut q_k) = (T::one(), T::one(), q);
+    for bit in (0..type_bits::<T>() - odd.leading_zeros() - 1).rev() {
+        // Op: `U_2k = U_k * V_k`, `V_2k = V_k^2 - 2 * Q^k`, `Q^2k = (Q^k)^2`
+        u = u.mul_mod(v, n);
+        v = sub_mod(v.residue(n), add_mod(q_k, q_k, n), n);
+        q_k = q_k.residue(n);
+
+        if (odd >> bit as usize) & T::one() == T::one() {
+            // Op: `U_2k+1 = (U_2k + V_2k) / 2`, `V_2k+1 = (D * U_2k + V_2k) / 2`, `Q^2k+1 = Q^2k * Q`
+            let (u_2k, v_2k) = (u, v);
+            u = half_mod(add_mod(u_2k, v_2k, n), n);
+            v = half_mod(add_mod(d_mod_n.mul_mod(u_2k, n), v_2k, n), n);
+            q_k = q_k.mul_mod(q, n);
+        }
+    }
+
+    if u == T::zero() || v == T::zero() {
+        return true;
+    }
+    for _ in 1..s {
+        // Op: `V_2k = V_k^2 - 2 * Q^k`
+        v = sub_mod(v.residue(n), add_mod(q_k, q_k, n), n);
+        if v == T::zero() {
+            return true;
+        }
+        q_k = q_k.residue(n);
+    }
+    false
+}
+
+/// The Jacobi symbol `(a/n)` of an odd `n`, as -1, 0 or 1.
+fn jacobi<T: QuadraticResidue>(mut a: T, mut n: T) -> i8 {
+    let small = |x: u8| T::from_u8(x).expect("Small values fit in every type.");
+    let (three, five, seven) = (small(3), small(5), small(7));
+    let mut result = 1;
+    a = a % n;
+    while a != T::zero() {
+        while a & T::one() == T::zero() {
+            a = a >> 1;
+            if n & seven == three || n & seven == five {
+                result = -result;
+            }
+        }
+        core::mem::swap(&mut a, &mut n);
+        if a & three == three && n & three == three {
+            result = -result;
+        }
+        a = a % n;
+    }
+    if n == T::one() { result } else { 0 }
+}
+
+/// Whether `n` is a perfect square, by Newton's method from a power of two above `sqrt(n)`.
+fn is_square<T: QuadraticResidue>(n: T) -> bool {
+    let bits = type_bits::<T>() - n.leading_zeros();
+    let mut x = T::one() << bits.div_ceil(2) as usize;
+    loop {
+        let y = (x + n / x) >> 1;
+        if y >= x {
+            return x * x == n;
+        }
+        x = y;
+    }
+}
+
+/// `(a + b) mod n` for `a, b < n`, without overflowing when `n` is near the top of the type.
+#[inline]
+fn add_mod<T: QuadraticResidue>(a: T, b: T, n: T) -> T {
+    if a >= n - b { a - (n - b) } else { a + b }
+}
+
+/// `(a - b) mod n` for `a, b < n`.
+#[inline]
+fn sub_mod<T: QuadraticResidue>(a: T, b: T, n: T) -> T {
+    if a >= b { a - b } else { n - (b - a) }
+}
+
+/// `a / 2 mod n` for `a < n` and an odd `n`, as `(a + n) / 2` for an odd `a`.
+#[inline]
+fn half_mod<T: QuadraticResidue>(a: T, n: T) -> T {
+    match a & T::one() == T::zero() {
+        true => a >> 1,
+        false => (a >> 1) + (n >> 1) + T::one(),
+    }
+}
+
+/// Compute `base^exponent mod modulus` by square and multiply.
+#[inline]
+fn pow_mod<T: QuadraticResidue>(mut base: T, mut exponent: T, modulus: T) -> T {
+    let mut result = T::one();
+    while exponent > T::zero() {
+        if exponent & T::one() == T::one() {
+            result = result.mul_mod(base, modulus);
+        }
+        base = base.residue(modulus);
+        exponent = exponent >> 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::string::ToString;
+
+    use rand::rngs::OsRng;
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn test_primes_3_mod_4() {
+        assert_eq!(prime_for_bits(0), 3);
+        assert_eq!(prime_for_bits(8), u8::MAX as u128 - 4);
+        assert_eq!(prime_for_bits(64), 18446744073709551427);
+
+        // check primes up to 64 bits, the is_prime crate is too slow to check all 128 bits
+        for bits in 2..=64 {
+            let prime = prime_for_bits(bits);
+            assert!(prime < 1 << bits, "{} doesn't fit in {} bits", prime, bits);
+            assert!(prime >= 1 << (bits - 1), "{} isn't the largest prime for {} bits", prime, bits);
+            assert_eq!(prime % 4, 3, "{} = 3 mod 4 doesn't hold", prime);
+            assert!(is_prime::is_prime(&prime.to_string()), "{} is not prime", prime);
+        }
+    }
+
+    #[test]
+    fn test_search_prime() {
+        // the search finds the primes of the prime table
+        for bits in 2..=128 {
+            let mask = u128::MAX >> (128 - bits);
+            assert_eq!(search_prime(mask), prime_for_bits(bits), "Wrong prime for {} bits", bits);
+            assert_eq!(prime_below::<u128>(bits), prime_for_bits(bits));
+        }
+        assert_eq!(search_prime(u8::MAX), 251);
+        assert_eq!(search_prime(u16::MAX), 65519);
+        assert_eq!(search_prime(u32::MAX), 4294967291);
+    }
+
+    #[test]
+    fn test_is_prime() {
+        // check every u16, and the values around the u8 and u16 boundaries
+        for n in 0..=u16::MAX {
+            assert_eq!(is_prime(n), is_prime::is_prime(&n.to_string()), "{}", n);
+        }
+        for n in 240..=u8::MAX {
+            assert_eq!(is_prime(n), is_prime::is_prime(&n.to_string()), "{}", n);
+        }
+
+        // check random values of each width, as odd values to skip the trivial cases
+        let mut rng = OsRng;
+        for _ in 0..2000 {
+            let n = rng.gen::<u32>() | 1;
+            assert_eq!(is_prime(n), is_prime::is_prime(&n.to_string()), "{}", n);
+            let n = rng.gen::<u64>() | 1;
+            assert_eq!(is_prime(n), is_prime::is_prime(&n.to_string()), "{}", n);
+            assert_eq!(is_prime(n as usize), is_prime(n));
+        }
+
+        // check the prime table at every width
+        for bits in 2..=128 {
+            let prime = prime_for_bits(bits);
+            assert!(is_prime(prime), "{} is not prime", prime);
+            if bits <= 64 {
+                assert!(is_prime(prime as u64));
+            }
+        }
+
+        // strong pseudoprimes to the smallest bases, and Carmichael numbers
+        for n in [2047u128, 3215031751, 341550071728321, 3825123056546413051, 318665857834031151167461, 3317044064679887385961981] {
+            assert!(!is_prime(n), "{} is composite", n);
+            if n <= u64::MAX as u128 {
+                assert!(!is_prime(n as u64), "{} is composite", n);
+            }
+        }
+        for n in [561u32, 1105, 1729, 2465, 2821, 6601, 8911] {
+            assert!(!is_prime(n), "{} is composite", n);
+        }
+        assert!(!is_prime(4759123141u64));
+
+        // products of two large primes
+        let p = 18446744073709551557u128;
+        assert!(is_prime(p));
+        assert!(!is_prime(p * 4294967291));
+        assert!(!is_prime(4294967291u64 * 4294967279));
+    }
+
+    #[test]
+    fn test_strong_lucas() {
+        // the strong Lucas test holds for every odd prime, and the few strong Lucas pseudoprimes
+        let pseudoprimes = [5459, 5777, 10877, 16109, 18971, 22499, 24569, 25199, 40309, 58519, 75077, 97439];
+        for n in (3..100_000u32).step_by(2) {
+            assert_eq!(strong_lucas(n), is_prime(n) || pseudoprimes.contains(&n), "{}", n);
+        }
+
+        // above 2^64, squares and the strong pseudoprimes to base 2 are composite
+        assert!(strong_lucas(prime_for_bits(100)));
+        assert!(!strong_lucas(8589934583u128 * 8589934583));
+        assert!(!strong_lucas(318665857834031151167461u128));
+        assert!(!strong_lucas(3317044064679887385961981u128));
+    }
+}
@@ -1,15 +1,36 @@
 use rand::RngCore;
 
-use crate::{RandomSequence, RandomSequenceBuilder};
+use crate::builder::{type_bits, QuadraticResidue};
+use crate::{
+    BitWidth, Bits, BitsPermutation, PortablePermutation, RandomSequence, RandomSequenceBuilder, RandomSequenceBuilderInit,
+    SequenceInt,
+};
+
+/// Generate a random u128 seed.
+#[inline]
+fn next_u128(rng: &mut impl RngCore) -> u128 {
+    ((rng.next_u64() as u128) << 64) | rng.next_u64() as u128
+}
+
+/// Generate a random seed of type `T`, from a u64 truncated to the type for up to 64 bits, or
+/// from as many u64s as fill the type.
+#[inline]
+pub(crate) fn next_seed<T: QuadraticResidue>(rng: &mut impl RngCore) -> T {
+    let bits = type_bits::<T>();
+    if bits <= 64 {
+        return T::from_u64(rng.next_u64() & (u64::MAX >> (64 - bits))).expect("Seed must fit in the type.");
+    }
+    (0..bits.div_ceil(64)).fold(T::zero(), |seed, _| {
+        (seed << 64) | T::from_u64(rng.next_u64()).expect("Types above 64 bits fit a u64.")
+    })
+}
 
 macro_rules! init_rand {
     ($type:ident, $tests:ident) => {
         impl RandomSequenceBuilder<$type> {
             /// Initialise a RandomSequenceBuilder from a random seed.
             pub fn rand(rng: &mut impl RngCore) -> Self {
-                let seed_base = rng.next_u64() as $type;
-                let seed_offset = rng.next_u64() as $type;
-                Self::new(seed_base, seed_offset)
+                <Self as RandomSequenceBuilderInit<$type>>::rand(rng)
             }
         }
 
@@ -45,3 +66,115 @@ init_rand!(u16, tests_u16);
 init_rand!(u32, tests_u32);
 init_rand!(u64, tests_u64);
 init_rand!(usize, tests_usize);
+init_rand!(u128, tests_u128);
+
+macro_rules! init_rand_signed {
+    ($type:ident, $unsigned:ident, $tests:ident) => {
+        impl RandomSequence<$type> {
+            /// Initialise a RandomSequence from a random seed.
+            pub fn rand(rng: &mut impl RngCore) -> Self {
+                RandomSequenceBuilder::<$unsigned>::rand(rng).into()
+            }
+        }
+
+        #[cfg(test)]
+        mod $tests {
+            use rand::rngs::OsRng;
+
+            use super::*;
+
+            #[test]
+            fn test_rand() {
+                let mut sequence = RandomSequence::<$type>::rand(&mut OsRng);
+                assert_ne!(sequence.next(), sequence.next());
+            }
+        }
+    };
+}
+
+init_rand_signed!(i8, u8, tests_i8);
+init_rand_signed!(i16, u16, tests_i16);
+init_rand_signed!(i32, u32, tests_i32);
+init_rand_signed!(i64, u64, tests_i64);
+init_rand_signed!(i128, u128, tests_i128);
+init_rand_signed!(isize, usize, tests_isize);
+
+impl<U, const BITS: u32> RandomSequence<U, BitsPermutation<U, BITS>>
+where
+    U: QuadraticResidue + SequenceInt<Unsigned = U>,
+    Bits<BITS>: BitWidth<Storage = U>,
+{
+    /// Initialise a RandomSequence of `BITS`-bit values from a random seed.
+    pub fn rand(rng: &mut impl RngCore) -> Self {
+        let seed_base = U::from_u128(next_u128(rng) >> (128 - BITS)).expect("Seed must fit in the storage type.");
+        let seed_offset = U::from_u128(next_u128(rng) >> (128 - BITS)).expect("Seed must fit in the storage type.");
+        Self::new(seed_base, seed_offset)
+    }
+}
+
+impl<U> RandomSequence<usize, PortablePermutation<U>>
+where
+    U: QuadraticResidue,
+    RandomSequenceBuilder<U>: RandomSequenceBuilderInit<U>,
+    Self: From<RandomSequenceBuilder<U>>,
+{
+    /// Initialise a portable RandomSequence from a random seed.
+    pub fn rand(rng: &mut impl RngCore) -> Self {
+        RandomSequenceBuilder::<U>::rand(rng).into()
+    }
+}
+
+#[cfg(test)]
+mod tests_bits {
+    use rand::rngs::OsRng;
+
+    use crate::RandomBitsSequence;
+
+    #[test]
+    fn test_rand() {
+        let mut sequence = RandomBitsSequence::<24>::rand(&mut OsRng);
+        assert_ne!(sequence.next(), sequence.next());
+        assert!(sequence.take(1000).all(|value| value < 1 << 24));
+
+        let sequence = RandomBitsSequence::<100>::rand(&mut OsRng);
+        assert!(sequence.take(1000).all(|value| value < 1 << 100));
+    }
+}
+
+#[cfg(test)]
+mod tests_portable {
+    use rand::rngs::OsRng;
+
+    use crate::PortableSequence;
+
+    #[test]
+    fn test_rand() {
+        let mut sequence = PortableSequence::<u32>::rand(&mut OsRng);
+        assert_ne!(sequence.next(), sequence.next());
+        assert!(sequence.take(1000).all(|value| value <= u32::MAX as usize));
+    }
+}
+
+#[cfg(test)]
+mod tests_init {
+    use rand::rngs::OsRng;
+
+    use super::*;
+
+    /// Construct a random builder generically, through [RandomSequenceBuilderInit].
+    fn rand<T>() -> RandomSequenceBuilder<T>
+    where
+        T: QuadraticResidue,
+        RandomSequenceBuilder<T>: RandomSequenceBuilderInit<T>,
+    {
+        RandomSequenceBuilder::<T>::rand(&mut OsRng)
+    }
+
+    #[test]
+    fn test_rand() {
+        assert_ne!(rand::<u16>(), rand::<u16>());
+        assert_ne!(rand::<u64>(), rand::<u64>());
+        assert_ne!(rand::<u128>(), rand::<u128>());
+        assert!(rand::<u128>().seed_base > u64::MAX as u128 || rand::<u128>().seed_offset > u64::MAX as u128);
+    }
+}
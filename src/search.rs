@@ -0,0 +1,373 @@
+use core::any::type_name;
+use core::fmt;
+use std::vec;
+
+use crate::builder::{domain_bits, type_bits, KeySchedule, QuadraticResidue, RandomSequenceBuilder};
+use crate::feistel::mix;
+use crate::primes::{prime_below, search_prime};
+
+/// Search for a new parameter set of a [RandomSequenceBuilder], for a bit width or a domain.
+///
+/// The search finds the largest prime `p < 2^k` satisfying `p = 3 mod 4` for the power of two
+/// range `0..2^k` of the domain, and then scores random candidates for the `init_base`,
+/// `init_offset` and `intermediate_xor` constants by their measured mixing quality, see
+/// [ParameterSpec::score]. The best candidate is returned as a [ParameterSpec], which builds the
+/// sequence and prints the equivalent Rust code. Requires the `std` feature.
+///
+/// The search is deterministic for the same [ParameterSearch::with_seed].
+///
+/// ```rust
+/// use rand_unique::ParameterSearch;
+///
+/// let spec = ParameterSearch::<u32>::new().with_domain(1_000_000).with_candidates(4).search();
+/// let sequence = spec.builder(7, 11).into_iter();
+/// assert!(sequence.n(0) < 1_000_000);
+///
+/// // prints `RandomSequenceBuilder::<u32>::try_from_spec(seed_base, seed_offset, ...)`
+/// println!("{}", spec);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParameterSearch<T>
+where
+    T: QuadraticResidue
+{
+    domain: Option<T>,
+    candidates: usize,
+    samples: usize,
+    seed: u64,
+}
+
+/// A parameter set found by a [ParameterSearch].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParameterSpec<T>
+where
+    T: QuadraticResidue
+{
+    /// The largest prime `prime = 3 mod 4` of the power of two range covering the domain.
+    pub prime: T,
+
+    /// The constant mixed into the seeds for the start index, the `init_base` of a [RandomSequenceBuilder].
+    pub init_base: T,
+
+    /// The constant mixed into the seeds for the offsets, the `init_offset` of a [RandomSequenceBuilder].
+    pub init_offset: T,
+
+    /// The constant mixed into the xors, the `intermediate_xor` of a [RandomSequenceBuilder].
+    pub intermediate_xor: T,
+
+    /// The domain searched, or `None` for the entire range of `T`.
+    pub domain: Option<T>,
+
+    /// The avalanche bias of the constants, the mean absolute deviation from 1/2 of the probability
+    /// that flipping a bit of the index flips each bit of the value, across random seeds. Lower is
+    /// better, and 0 is ideal.
+    pub score: f64,
+}
+
+impl<T> ParameterSearch<T>
+where
+    T: QuadraticResidue
+{
+    /// Search the entire range of `T`, scoring 16 candidates over 64 samples each.
+    pub fn new() -> Self {
+        Self {
+            domain: None,
+            candidates: 16,
+            samples: 64,
+            seed: 0,
+        }
+    }
+
+    /// Search for the parameters of the values `0..domain`, see [RandomSequenceBuilder::with_domain].
+    ///
+    /// # Panics
+    ///
+    /// If `domain` is zero.
+    pub fn with_domain(mut self, domain: T) -> Self {
+        assert!(domain > T::zero(), "The domain must contain at least one value.");
+        self.domain = Some(domain);
+        self
+    }
+
+    /// Search for the parameters of the `bits`-bit values `0..2^bits`, see
+    /// [RandomSequenceBuilder::with_bits].
+    ///
+    /// # Panics
+    ///
+    /// If `bits` is zero or wider than `T`.
+    pub fn with_bits(mut self, bits: u32) -> Self {
+        assert!(bits > 0 && bits <= type_bits::<T>(), "The bit width must be from 1 to {} bits.", type_bits::<T>());
+        self.domain = (bits < type_bits::<T>()).then(|| T::one() << bits as usize);
+        self
+    }
+
+    /// The number of random candidates to score, where more candidates find a better score.
+    ///
+    /// # Panics
+    ///
+    /// If `candidates` is zero.
+    pub fn with_candidates(mut self, candidates: usize) -> Self {
+        assert!(candidates > 0, "The search requires at least one candidate.");
+        self.candidates = candidates;
+        self
+    }
+
+    /// The number of random indices and seeds to score each candidate over, where more samples
+    /// measure the score more precisely.
+    ///
+    /// # Panics
+    ///
+    /// If `samples` is zero.
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        assert!(samples > 0, "The search requires at least one sample.");
+        self.samples = samples;
+        self
+    }
+
+    /// Seed the generator of the candidates and samples, for a different but repeatable search.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Find the prime, and the best scoring candidate constants.
+    pub fn search(&self) -> ParameterSpec<T> {
+        let mut rng = SplitMix64(self.seed);
+        let prime = search_prime(self.mask());
+        (0..self.candidates)
+            .map(|_| {
+                let mut spec = ParameterSpec {
+                    prime,
+                    init_base: rng.next(),
+                    init_offset: rng.next(),
+                    intermediate_xor: rng.next(),
+                    domain: self.domain,
+                    score: 0.0,
+                };
+                spec.score = self.score(&spec, &mut rng);
+                spec
+            })
+            .min_by(|a, b| a.score.total_cmp(&b.score))
+            .expect("The search requires at least one candidate.")
+    }
+
+    /// The bit mask of the power of two range `0..2^k` covering the domain.
+    fn mask(&self) -> T {
+        match self.domain {
+            Some(domain) => T::max_value() >> (type_bits::<T>() - domain_bits(domain)) as usize,
+            None => T::max_value(),
+        }
+    }
+
+    /// Measure the avalanche bias of a candidate, see [ParameterSpec::score].
+    fn score(&self, spec: &ParameterSpec<T>, rng: &mut SplitMix64) -> f64 {
+        let bits = self.mask().count_ones() as usize;
+        let mut trials = vec![0u32; bits];
+        let mut flips = vec![0u32; bits * bits];
+        for _ in 0..self.samples {
+            // the constants are always combined with seeds, so vary the seeds too
+            let config = spec.builder(rng.next(), rng.next());
+            let keys = config.keys();
+            let index = loop {
+                let index = rng.next::<T>() & self.mask();
+                if config.in_domain(index) {
+                    break index;
+                }
+            };
+            let value = config.permute(index, &keys);
+            for i in 0..bits {
+                let flipped = index ^ (T::one() << i);
+                if !config.in_domain(flipped) {
+                    continue;
+                }
+                let diff = value ^ config.permute(flipped, &keys);
+                trials[i] += 1;
+                for j in 0..bits {
+                    if (diff >> j) & T::one() == T::one() {
+                        flips[i * bits + j] += 1;
+                    }
+                }
+            }
+        }
+
+        let mut bias = 0.0;
+        let mut count = 0;
+        for i in (0..bits).filter(|i| trials[*i] > 0) {
+            for j in 0..bits {
+                bias += (flips[i * bits + j] as f64 / trials[i] as f64 - 0.5).abs();
+                count += 1;
+            }
+        }
+        bias / count.max(1) as f64
+    }
+}
+
+impl<T> Default for ParameterSearch<T>
+where
+    T: QuadraticResidue
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ParameterSpec<T>
+where
+    T: QuadraticResidue
+{
+    /// Initialise a [RandomSequenceBuilder] with these parameters from a specific seed pair, with
+    /// the same defaults as `RandomSequenceBuilder::new`.
+    pub fn builder(&self, seed_base: T, seed_offset: T) -> RandomSequenceBuilder<T> {
+        let config = RandomSequenceBuilder::try_from_spec(
+            seed_base,
+            seed_offset,
+            self.init_base,
+            self.init_offset,
+            self.full_prime(),
+            self.intermediate_xor,
+        )
+        .expect("The prime table must be valid.")
+        .with_key_schedule(KeySchedule::Seeded)
+        .with_fold_tail(true);
+        match self.domain {
+            // the domain selects the searched prime, so the prime never needs to be passed unchecked
+            Some(domain) => config.with_domain(domain),
+            None => config,
+        }
+    }
+
+    /// The prime passed to `try_from_spec`, which must cover the entire range of `T` when
+    /// `with_domain` later selects the searched prime.
+    fn full_prime(&self) -> T {
+        match self.domain {
+            Some(_) => prime_below(type_bits::<T>()),
+            None => self.prime,
+        }
+    }
+}
+
+impl<T> fmt::Display for ParameterSpec<T>
+where
+    T: QuadraticResidue + fmt::Display + fmt::LowerHex
+{
+    /// Print the Rust code to build the sequence from a `seed_base` and `seed_offset`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RandomSequenceBuilder::<{}>::try_from_spec(seed_base, seed_offset, {:#x}, {:#x}, {}, {:#x})?\n    \
+             .with_key_schedule(KeySchedule::Seeded)\n    \
+             .with_fold_tail(true)",
+            type_name::<T>(),
+            self.init_base,
+            self.init_offset,
+            self.full_prime(),
+            self.intermediate_xor,
+        )?;
+        if let Some(domain) = self.domain {
+            write!(f, "\n    .with_domain({})", domain)?;
+        }
+        Ok(())
+    }
+}
+
+/// The splitmix64 generator, for repeatable candidates and samples without the `rand` feature.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// The next random value of type `T`, from as many 64-bit draws as cover its width.
+    fn next<T: QuadraticResidue>(&mut self) -> T {
+        let bits = type_bits::<T>();
+        let mut value = T::zero();
+        for _ in 0..bits.div_ceil(64) {
+            self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+            let draw = T::from_u64(mix(self.0) & (u64::MAX >> 64u32.saturating_sub(bits)));
+            let draw = draw.expect("The draw is masked to the width of the type.");
+            value = if bits > 64 { (value << 64) | draw } else { draw };
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::string::ToString;
+
+    use bnum::types::U256;
+
+    use super::*;
+
+    macro_rules! test_search {
+        ($name:ident, $type:ident, $domain:expr) => {
+            #[test]
+            fn $name() {
+                let search = ParameterSearch::<$type>::new().with_candidates(4).with_samples(16);
+                let spec = search.search();
+                assert_eq!(spec, search.search(), "The search must be repeatable.");
+                assert_ne!(spec, search.with_seed(1).search());
+                assert!(spec.score >= 0.0 && spec.score < 0.5);
+                assert_eq!(spec.builder(7, 11).validate(), Ok(()));
+
+                // a domain uses the prime of its power of two range
+                let spec = search.with_domain($domain).search();
+                let sequence = spec.builder(7, 11).into_iter();
+                assert_eq!(spec.builder(7, 11).validate(), Ok(()));
+                assert_eq!(spec.builder(7, 11).prime, spec.prime);
+                let nums: HashSet<$type> = sequence.clone().take(1000).collect();
+                assert_eq!(nums.len(), if $domain < 1000 { $domain as usize } else { 1000 });
+                assert!(nums.iter().all(|num| *num < $domain));
+                assert!(spec.to_string().ends_with(&std::format!(".with_domain({})", $domain)));
+            }
+        };
+    }
+
+    test_search!(test_u8_search, u8, 100);
+    test_search!(test_u16_search, u16, 5000);
+    test_search!(test_u32_search, u32, 1_000_000);
+    test_search!(test_u64_search, u64, 1u64 << 40);
+    test_search!(test_u128_search, u128, 1u128 << 100);
+
+    #[test]
+    fn test_search_score() {
+        // the identity mixes nothing, and scores far worse than the searched constants
+        let spec = ParameterSearch::<u16>::new().with_candidates(8).with_samples(64).search();
+        let search = ParameterSearch::<u16>::new().with_samples(64);
+        let mut rng = SplitMix64(0);
+        let identity = ParameterSpec { prime: 65519, init_base: 0, init_offset: 0, intermediate_xor: 0, domain: None, score: 0.0 };
+        assert!(search.score(&identity, &mut rng) > spec.score);
+        assert!(spec.score < 0.1, "Poor mixing score {}", spec.score);
+
+        // the printed spec builds the same sequence
+        let printed = spec.to_string();
+        assert!(printed.starts_with("RandomSequenceBuilder::<u16>::try_from_spec(seed_base, seed_offset, "));
+        let config = RandomSequenceBuilder::<u16>::try_from_spec(7, 11, spec.init_base, spec.init_offset, spec.prime, spec.intermediate_xor)
+            .unwrap()
+            .with_key_schedule(KeySchedule::Seeded)
+            .with_fold_tail(true);
+        assert_eq!(config, spec.builder(7, 11));
+        assert!(printed.contains(&std::format!("{:#x}", spec.intermediate_xor)));
+    }
+
+    #[test]
+    fn test_splitmix_widths() {
+        // narrow types keep the low bits of a draw, and wide types concatenate several draws
+        let draw = |seed| SplitMix64(seed).next::<u64>();
+        assert_eq!(SplitMix64(7).next::<u8>(), draw(7) as u8);
+        assert_eq!(SplitMix64(7).next::<u32>(), draw(7) as u32);
+        let (mut rng, mut wide) = (SplitMix64(7), SplitMix64(7));
+        let (high, low) = (rng.next::<u128>(), rng.next::<u128>());
+        let wide = wide.next::<U256>();
+        assert_eq!(wide >> 128u32, U256::from(high));
+        assert_eq!(wide & U256::from(u128::MAX), U256::from(low));
+    }
+
+    #[test]
+    fn test_wide_search() {
+        let search = ParameterSearch::<U256>::new().with_candidates(2).with_samples(2);
+        let spec = search.search();
+        assert_eq!(spec.prime, U256::MAX - U256::from(188u8));
+        assert!(spec.init_base >> 192u32 != U256::ZERO, "The constants must cover the whole width.");
+        assert_eq!(spec.builder(U256::from(7u8), U256::from(11u8)).validate(), Ok(()));
+        assert!(spec.to_string().starts_with("RandomSequenceBuilder::<bnum::"));
+    }
+}
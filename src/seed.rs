@@ -3,13 +3,16 @@ use crate::sequence::RandomSequence;
 
 macro_rules! seed_sequence {
     ($type:ident) => {
+        seed_sequence!($type, $type);
+    };
+    ($type:ident, $unsigned:ident) => {
         impl RandomSequence<$type> {
             /// Initialise a random sequence from the seeds.
             ///
             /// These seeds should be two uniformly random numbers across the u64 space.
             pub fn new(seed_base: $type, seed_offset: $type) -> Self {
-                let config = RandomSequenceBuilder::<$type>::new(seed_base, seed_offset);
-                config.into_iter()
+                let config = RandomSequenceBuilder::<$unsigned>::new(seed_base as $unsigned, seed_offset as $unsigned);
+                config.into()
             }
         }
     };
@@ -20,3 +23,10 @@ seed_sequence!(u16);
 seed_sequence!(u32);
 seed_sequence!(u64);
 seed_sequence!(usize);
+seed_sequence!(u128);
+seed_sequence!(i8, u8);
+seed_sequence!(i16, u16);
+seed_sequence!(i32, u32);
+seed_sequence!(i64, u64);
+seed_sequence!(i128, u128);
+seed_sequence!(isize, usize);
@@ -1,4 +1,8 @@
-use crate::builder::{QuadraticResidue, RandomSequenceBuilder};
+use num_traits::{Bounded, CheckedSub, FromPrimitive, One, WrappingAdd, WrappingSub, Zero};
+
+use crate::builder::{to_u128, RandomSequenceBuilder};
+use crate::permutation::{BuilderPermutation, Permutation};
+use crate::signed::SequenceInt;
 
 /// Generate a deterministic pseudo-random sequence of unique numbers.
 ///
@@ -9,48 +13,62 @@ use crate::builder::{QuadraticResidue, RandomSequenceBuilder};
 /// - The sequence will only include each number once (every index is unique).
 /// - Computing the value for any random index in the sequence is an O(1) operation.
 ///
+/// Signed sequences, such as `RandomSequence<i32>`, are defined by the [RandomSequenceBuilder] of
+/// the unsigned type with the same width. Signed indices and values share the bit pattern of their
+/// unsigned counterparts, so index `-1` is the last element of the sequence.
+///
 /// Based on the article by @preshing:
 /// Article: http://preshing.com/20121224/how-to-generate-a-sequence-of-unique-random-integers/
 /// Source: https://github.com/preshing/RandomSequence/blob/master/randomsequence.h
 #[derive(Debug, Clone)]
-pub struct RandomSequence<T>
+pub struct RandomSequence<T, P = BuilderPermutation<<T as SequenceInt>::Unsigned>>
 where
-    T: QuadraticResidue
+    T: SequenceInt
 {
-    /// The config/builder holds the parameters that define the sequence.
-    pub config: RandomSequenceBuilder<T>,
+    /// The permutation of the indices that defines the sequence, see [Permutation].
+    pub(crate) permutation: P,
 
     /// Internal iterator-only state.
-    pub(crate) start_index: T,
-    pub(crate) current_index: T,
-    pub(crate) intermediate_offset: T,
+    pub(crate) current_index: T::Unsigned,
 
     /// The end marker, required for the ExactSizeIterator so that we terminate correctly.
     pub(crate) ended: bool,
 }
 
-impl<T> RandomSequence<T>
+impl<T, P> RandomSequence<T, P>
 where
-    T: QuadraticResidue
+    T: SequenceInt,
+    P: Permutation<T>,
 {
+    /// Generate the sequence of a custom [Permutation], starting from index 0.
+    pub fn from_permutation(permutation: P) -> Self {
+        RandomSequence {
+            permutation,
+            current_index: T::Unsigned::zero(),
+            ended: false,
+        }
+    }
+
+    /// The permutation of the indices that defines the sequence, read only so it can't change
+    /// under the cursor.
+    #[inline]
+    pub fn permutation(&self) -> &P {
+        &self.permutation
+    }
+
     /// Get the next element in the sequence.
+    #[allow(clippy::should_implement_trait)]
     #[inline]
     pub fn next(&mut self) -> Option<T> {
-        let next = self.n_internal(self.start_index.wrapping_add(&self.current_index));
-        self.current_index = match self.current_index.checked_add(&T::one()) {
-            Some(v) => {
-                self.ended = false;
-                v
-            },
-            None => {
-                if !self.ended {
-                    self.ended = true;
-                    self.current_index
-                } else {
-                    return None
-                }
-            },
-        };
+        let next = self.permutation.apply_sequential(self.current_index);
+        if self.current_index < self.permutation.max_index() {
+            self.current_index = self.current_index + T::Unsigned::one();
+            self.ended = false;
+        } else if !self.ended {
+            self.ended = true;
+        } else {
+            return None;
+        }
         Some(next)
     }
 
@@ -60,8 +78,8 @@ where
     /// exact size iterator if it had reached the end.
     #[inline]
     pub fn wrapping_next(&mut self) -> T {
-        let next = self.n_internal(self.start_index.wrapping_add(&self.current_index));
-        self.current_index = self.current_index.wrapping_add(&T::one());
+        let next = self.permutation.apply_sequential(self.current_index);
+        self.current_index = self.add_index(self.current_index, T::Unsigned::one());
         next
     }
 
@@ -69,43 +87,155 @@ where
     #[inline]
     pub fn prev(&mut self) -> Option<T> {
         // decrement then compute, opposite to next()
-        self.current_index = match self.current_index.checked_sub(&T::one()) {
-            Some(v) => v,
-            None => return None,
-        };
+        self.current_index = self.current_index.checked_sub(&T::Unsigned::one())?;
         self.ended = false;
-        Some(self.n_internal(self.start_index.wrapping_add(&self.current_index)))
+        Some(self.permutation.apply_sequential(self.current_index))
     }
 
     /// Get the previous element in the sequence, cycling the sequence once we reach the start.
     #[inline]
     pub fn wrapping_prev(&mut self) -> T {
         // decrement then compute, opposite to next()
-        self.current_index = self.current_index.wrapping_sub(&T::one());
-        self.n_internal(self.start_index.wrapping_add(&self.current_index))
+        self.current_index = self.sub_index(self.current_index, T::Unsigned::one());
+        self.permutation.apply_sequential(self.current_index)
+    }
+
+    /// Get the next element in the sequence without moving the cursor, the element that
+    /// [RandomSequence::next] returns next.
+    #[inline]
+    pub fn peek(&self) -> Option<T> {
+        match self.ended {
+            false => Some(self.permutation.apply(self.current_index)),
+            true => None,
+        }
+    }
+
+    /// Get the previous element in the sequence without moving the cursor, the element that
+    /// [RandomSequence::prev] returns next.
+    #[inline]
+    pub fn peek_back(&self) -> Option<T> {
+        let index = self.current_index.checked_sub(&T::Unsigned::one())?;
+        Some(self.permutation.apply(index))
+    }
+
+    /// Skip `n` elements and get the next, the same as calling [RandomSequence::next] `n + 1`
+    /// times, but in O(1) by moving the cursor directly.
+    #[inline]
+    fn nth_next(&mut self, n: usize) -> Option<T> {
+        if self.ended {
+            return None;
+        }
+        let max_index = self.permutation.max_index();
+        match T::Unsigned::from_usize(n) {
+            Some(n) if n <= max_index - self.current_index => {
+                self.current_index = self.current_index + n;
+                self.next()
+            }
+            // skipped past the last element, which exhausts the sequence
+            _ => {
+                self.current_index = max_index;
+                self.ended = true;
+                None
+            }
+        }
+    }
+
+    /// Skip `n` elements and get the previous, the same as calling [RandomSequence::prev] `n + 1`
+    /// times, but in O(1) by moving the cursor directly.
+    #[inline]
+    fn nth_prev(&mut self, n: usize) -> Option<T> {
+        let steps = T::Unsigned::from_usize(n).map_or(self.current_index, |n| n.min(self.current_index));
+        if steps > T::Unsigned::zero() {
+            self.current_index = self.current_index - steps;
+            self.ended = false;
+        }
+        self.prev()
+    }
+
+    /// The last element of the sequence, or `None` if the sequence has been exhausted.
+    #[inline]
+    fn last_value(&self) -> Option<T> {
+        match self.ended {
+            false => Some(self.permutation.apply(self.permutation.max_index())),
+            true => None,
+        }
     }
 
     /// Get the nth element in the sequence.
+    ///
+    /// For sequences with a [RandomSequenceBuilder::domain], the index wraps around the domain.
     #[inline]
     pub fn n(&self, index: T) -> T {
-        let actual_index = self.start_index.wrapping_add(&index);
-        self.n_internal(actual_index)
+        self.permutation.apply(self.add_index(T::Unsigned::zero(), index.to_unsigned()))
+    }
+
+    /// Get the elements at many indices, the same values as [RandomSequence::n] for each index.
+    ///
+    /// The default permutation of up to 32 bits evaluates several indices at once with SSE2 or
+    /// AVX2 on x86_64, detecting AVX2 at runtime with the `std` feature, and of up to 64 bits with
+    /// AVX2 only, see [Permutation::apply_many]. `u128` sequences, custom primes too far below a
+    /// power of two, and the other backends compute each value with the scalar permutation, no
+    /// faster than calling `n` for each index.
+    ///
+    /// # Panics
+    ///
+    /// If `indices` and `values` have different lengths.
+    pub fn n_many(&self, indices: &[T], values: &mut [T]) {
+        assert_eq!(indices.len(), values.len(), "The indices and values must have the same length.");
+        let mut batch = [T::Unsigned::zero(); BATCH];
+        for (indices, values) in indices.chunks(BATCH).zip(values.chunks_mut(BATCH)) {
+            for (slot, index) in batch.iter_mut().zip(indices) {
+                *slot = self.add_index(T::Unsigned::zero(), index.to_unsigned());
+            }
+            self.permutation.apply_many(&batch[..indices.len()], values);
+        }
     }
 
-    /// Get the nth element in the sequence, but using the absolute index rather than relative to `start_index`.
+    /// Fill `values` with the consecutive elements from `start_index`, the same values as
+    /// `n(start_index + i)`, wrapping around the end of the sequence.
     ///
-    /// `qpr(qpr(index + intermediate_offset) ^ intermediate_xor)`
-    #[inline(always)]
-    fn n_internal(&self, index: T) -> T {
-        let inner_residue = self.config.permute_qpr(index).wrapping_add(&self.intermediate_offset);
-        self.config.permute_qpr(inner_residue ^ self.config.intermediate_xor)
+    /// Vectorised for the default permutation of up to 64 bits on x86_64 only, as for
+    /// [RandomSequence::n_many].
+    pub fn fill(&self, values: &mut [T], start_index: T) {
+        let max_index = self.permutation.max_index();
+        let mut index = self.add_index(T::Unsigned::zero(), start_index.to_unsigned());
+        let mut batch = [T::Unsigned::zero(); BATCH];
+        for values in values.chunks_mut(BATCH) {
+            for slot in &mut batch[..values.len()] {
+                *slot = index;
+                index = if index == max_index { T::Unsigned::zero() } else { index + T::Unsigned::one() };
+            }
+            self.permutation.apply_many(&batch[..values.len()], values);
+        }
+    }
+
+    /// Get the index in the sequence of a given value, the inverse of [RandomSequence::n].
+    ///
+    /// Every value in the sequence appears exactly once, so `sequence.n(sequence.index_of(value)) == value`.
+    ///
+    /// # Panics
+    ///
+    /// If `value` isn't part of the sequence, see [RandomSequence::contains], or the [Permutation]
+    /// can't be inverted.
+    #[inline]
+    pub fn index_of(&self, value: T) -> T {
+        let index = self.permutation.invert(value).expect("The value isn't part of the sequence.");
+        T::from_unsigned(index)
+    }
+
+    /// Check whether a value appears in the sequence.
+    ///
+    /// Always true unless the sequence has a [RandomSequenceBuilder::domain] or range set.
+    #[inline]
+    pub fn contains(&self, value: T) -> bool {
+        self.permutation.contains(value)
     }
 
     /// Get the current position in the sequence. Will return `None` if the sequence has been exhausted.
     #[inline]
     pub fn index(&self) -> Option<T> {
         match self.ended {
-            false => Some(self.current_index),
+            false => Some(T::from_unsigned(self.current_index)),
             true => None,
         }
     }
@@ -116,19 +246,80 @@ where
         self.ended
     }
 
+    /// The number of elements left in the sequence, the true count of a `u64` sequence of `2^64`
+    /// elements where the `usize` of the iterator's `size_hint` can't hold it.
+    ///
+    /// Saturates at `u128::MAX` for a full `u128` sequence of `2^128` elements.
+    #[inline]
+    pub fn remaining(&self) -> u128 {
+        match self.ended {
+            false => to_u128(self.permutation.max_index() - self.current_index).saturating_add(1),
+            true => 0,
+        }
+    }
+
     /// Set the index for the iterator.
     ///
-    /// If the iterator was exhausted, this will reset it to the index set.
+    /// If the iterator was exhausted, this will reset it to the index set. For sequences with a
+    /// [RandomSequenceBuilder::domain], the index wraps around the domain.
     #[inline]
     pub fn set_index(&mut self, index: T) {
-        self.current_index = index;
+        self.current_index = self.add_index(T::Unsigned::zero(), index.to_unsigned());
         self.ended = false;
     }
+
+    /// Compute `(index + offset) mod (max_index + 1)`, where `index` is already within the sequence.
+    #[inline]
+    fn add_index(&self, index: T::Unsigned, offset: T::Unsigned) -> T::Unsigned {
+        let max_index = self.permutation.max_index();
+        if max_index == T::Unsigned::max_value() {
+            return index.wrapping_add(&offset);
+        }
+        let len = max_index + T::Unsigned::one();
+        let offset = offset % len;
+        if index >= len - offset {
+            index - (len - offset)
+        } else {
+            index + offset
+        }
+    }
+
+    /// Compute `(index - offset) mod (max_index + 1)`, where `index` is already within the sequence.
+    #[inline]
+    fn sub_index(&self, index: T::Unsigned, offset: T::Unsigned) -> T::Unsigned {
+        let max_index = self.permutation.max_index();
+        if max_index == T::Unsigned::max_value() {
+            return index.wrapping_sub(&offset);
+        }
+        let len = max_index + T::Unsigned::one();
+        let offset = offset % len;
+        if index < offset {
+            index + (len - offset)
+        } else {
+            index - offset
+        }
+    }
+}
+
+/// The number of indices [RandomSequence::n_many] and [RandomSequence::fill] pass to the
+/// [Permutation] at once.
+pub(crate) const BATCH: usize = 64;
+
+/// The size hint of a sequence with `remaining + 1` values left, where `usize_max` is `usize::MAX`.
+///
+/// The remaining length may not fit in a usize, such as `usize::MAX + 1` for a full sequence. Takes
+/// `usize_max` as a parameter so the 32-bit hints can be checked on 64-bit targets.
+#[inline]
+pub(crate) fn unsized_hint(remaining: u128, usize_max: u128) -> (usize, Option<usize>) {
+    match remaining < usize_max {
+        true => (remaining as usize + 1, Some(remaining as usize + 1)),
+        false => (usize_max as usize, None),
+    }
 }
 
 macro_rules! impl_unsized_iterator {
     ($T:ident) => {
-        impl Iterator for RandomSequence<$T> {
+        impl<P: Permutation<$T>> Iterator for RandomSequence<$T, P> {
             type Item = $T;
 
             #[inline]
@@ -138,7 +329,35 @@ macro_rules! impl_unsized_iterator {
 
             #[inline]
             fn size_hint(&self) -> (usize, Option<usize>) {
-                ($T::MAX as usize - self.current_index as usize, None)
+                if self.ended {
+                    return (0, Some(0));
+                }
+                unsized_hint((self.permutation.max_index() - self.current_index) as u128, usize::MAX as u128)
+            }
+
+            /// Moves the cursor directly in O(1), which `skip` and `step_by` use to jump between elements.
+            #[inline]
+            fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                self.nth_next(n)
+            }
+
+            #[inline]
+            fn last(self) -> Option<Self::Item> {
+                self.last_value()
+            }
+
+            /// # Panics
+            ///
+            /// If more than `usize::MAX` elements remain, such as in a full `u64` sequence.
+            #[inline]
+            fn count(self) -> usize {
+                match self.ended {
+                    false => usize::try_from(self.permutation.max_index() - self.current_index)
+                        .ok()
+                        .and_then(|remaining| remaining.checked_add(1))
+                        .expect("The remaining length of the sequence doesn't fit in a usize."),
+                    true => 0,
+                }
             }
         }
     };
@@ -146,7 +365,7 @@ macro_rules! impl_unsized_iterator {
 
 macro_rules! impl_exact_size_iterator {
     ($T:ident) => {
-        impl Iterator for RandomSequence<$T> {
+        impl<P: Permutation<$T>> Iterator for RandomSequence<$T, P> {
             type Item = $T;
 
             #[inline]
@@ -156,11 +375,31 @@ macro_rules! impl_exact_size_iterator {
 
             #[inline]
             fn size_hint(&self) -> (usize, Option<usize>) {
-                ($T::MAX as usize + 1 - self.current_index as usize, Some($T::MAX as usize + 1 - self.current_index as usize))
+                let remaining = match self.ended {
+                    false => self.permutation.max_index() as usize + 1 - self.current_index as usize,
+                    true => 0,
+                };
+                (remaining, Some(remaining))
+            }
+
+            /// Moves the cursor directly in O(1), which `skip` and `step_by` use to jump between elements.
+            #[inline]
+            fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                self.nth_next(n)
+            }
+
+            #[inline]
+            fn last(self) -> Option<Self::Item> {
+                self.last_value()
+            }
+
+            #[inline]
+            fn count(self) -> usize {
+                self.len()
             }
         }
 
-        impl ExactSizeIterator for RandomSequence<$T> {}
+        impl<P: Permutation<$T>> ExactSizeIterator for RandomSequence<$T, P> {}
     };
 }
 
@@ -173,25 +412,52 @@ impl_exact_size_iterator!(u32);
 impl_unsized_iterator!(u32);
 impl_unsized_iterator!(u64);
 impl_unsized_iterator!(usize);
+impl_unsized_iterator!(u128);
+impl_exact_size_iterator!(i8);
+impl_exact_size_iterator!(i16);
+#[cfg(target_pointer_width = "64")]
+impl_exact_size_iterator!(i32);
+#[cfg(target_pointer_width = "32")]
+impl_unsized_iterator!(i32);
+impl_unsized_iterator!(i64);
+impl_unsized_iterator!(isize);
+impl_unsized_iterator!(i128);
 
-impl<T> DoubleEndedIterator for RandomSequence<T>
+impl<T, P> DoubleEndedIterator for RandomSequence<T, P>
 where
-    T: QuadraticResidue,
-    RandomSequence<T>: Iterator<Item = T>,
+    T: SequenceInt,
+    P: Permutation<T>,
+    RandomSequence<T, P>: Iterator<Item = T>,
 {
     #[inline]
     fn next_back(&mut self) -> Option<Self::Item> {
         self.prev()
     }
+
+    #[inline]
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.nth_prev(n)
+    }
 }
 
-impl<T> From<RandomSequenceBuilder<T>> for RandomSequence<T>
+impl<T> From<RandomSequenceBuilder<T::Unsigned>> for RandomSequence<T>
 where
-    T: QuadraticResidue,
-    RandomSequence<T>: Iterator<Item = T>,
+    T: SequenceInt,
 {
-    fn from(value: RandomSequenceBuilder<T>) -> Self {
-        value.into_iter()
+    fn from(value: RandomSequenceBuilder<T::Unsigned>) -> Self {
+        RandomSequence::from_permutation(BuilderPermutation::from(value))
+    }
+}
+
+impl<T> RandomSequence<T>
+where
+    T: SequenceInt,
+{
+    /// The config/builder that holds the parameters that define the sequence, which can be
+    /// serialized and restored with [RandomSequence]'s `From<RandomSequenceBuilder>`.
+    #[inline]
+    pub fn config(&self) -> &RandomSequenceBuilder<T::Unsigned> {
+        self.permutation.config()
     }
 }
 
@@ -204,38 +470,52 @@ mod tests {
     use statrs::distribution::{ChiSquared, ContinuousCDF};
 
     use super::*;
+    use crate::builder::{test_configs, Backend, KeySchedule};
 
     fn is_send<T: Send>() {}
     fn is_sync<T: Sync>() {}
 
     macro_rules! test_sequence {
         ($name:ident, $type:ident, $check:literal) => {
+            test_sequence!($name, $type, $type, $check);
+        };
+        ($name:ident, $type:ident, $unsigned:ident, $check:literal) => {
             #[test]
             fn $name() {
-                let config = RandomSequenceBuilder::<$type>::new(0, 0);
-                let sequence = config.into_iter();
+                // the last index, which is -1 for signed types
+                const LAST: $type = (0 as $type).wrapping_sub(1);
+
+                let config = RandomSequenceBuilder::<$unsigned>::new(0, 0);
+                let sequence = RandomSequence::<$type>::from(config);
 
                 for (i, num) in std::iter::zip(0..10, sequence.clone()) {
                     assert_eq!(sequence.n(i as $type), num);
                 }
 
                 for (i, num) in std::iter::zip(0..10, sequence.clone().rev()) {
-                    assert_eq!(sequence.n($type::MAX.wrapping_sub(i as $type)), num);
+                    assert_eq!(sequence.n(LAST.wrapping_sub(i as $type)), num);
+                }
+
+                // check index_of inverts n, both on the sequence and on the builder
+                for i in (0..1000).map(|i| i as $type).chain(LAST - 100..=LAST) {
+                    let num = sequence.n(i);
+                    assert_eq!(sequence.index_of(num), i);
+                    assert_eq!(config.index_of(num as $unsigned), i as $unsigned);
                 }
 
                 // check the exact size iterator ends correctly for u8 and u16
-                if ($type::MAX as usize) < $check {
-                    let nums_vec: Vec<$type> = config.into_iter().take($check + 10).collect();
-                    assert_eq!(nums_vec.len(), ($type::MAX as usize).saturating_add(1));
+                if (LAST as $unsigned as usize) < $check {
+                    let nums_vec: Vec<$type> = RandomSequence::<$type>::from(config).take($check + 10).collect();
+                    assert_eq!(nums_vec.len(), (LAST as $unsigned as usize).saturating_add(1));
                 }
 
                 // check that we see each value only once
-                let nums: HashSet<$type> = config.into_iter().take($check).collect();
+                let nums: HashSet<$type> = RandomSequence::<$type>::from(config).take($check).collect();
                 assert_eq!(nums.len(), $check);
 
                 // check exhaustion
                 {
-                    let mut sequence = config.into_iter();
+                    let mut sequence = RandomSequence::<$type>::from(config);
 
                     // initial index = 0
                     assert!(!sequence.exhausted());
@@ -243,9 +523,9 @@ mod tests {
                     let first = sequence.next().unwrap();
 
                     // final index
-                    sequence.set_index($type::MAX);
+                    sequence.set_index(LAST);
                     assert!(!sequence.exhausted());
-                    assert_eq!(sequence.index(), Some($type::MAX));
+                    assert_eq!(sequence.index(), Some(LAST));
                     let last = sequence.next().unwrap();
 
                     // exhausted
@@ -254,9 +534,9 @@ mod tests {
                     assert!(sequence.next().is_none());
 
                     // reset
-                    sequence.set_index($type::MAX);
+                    sequence.set_index(LAST);
                     assert!(!sequence.exhausted());
-                    assert_eq!(sequence.index(), Some($type::MAX));
+                    assert_eq!(sequence.index(), Some(LAST));
                     assert_eq!(sequence.next(), Some(last));
                     assert!(sequence.exhausted());  // gets exhausted again
 
@@ -279,27 +559,475 @@ mod tests {
     test_sequence!(test_u32_sequence, u32, 100_000);
     test_sequence!(test_u64_sequence, u64, 100_000);
     test_sequence!(test_usize_sequence, usize, 100_000);
+    test_sequence!(test_u128_sequence, u128, 100_000);
+    test_sequence!(test_i8_sequence, i8, u8, 256);
+    test_sequence!(test_i16_sequence, i16, u16, 65536);
+    test_sequence!(test_i32_sequence, i32, u32, 100_000);
+    test_sequence!(test_i64_sequence, i64, u64, 100_000);
+    test_sequence!(test_isize_sequence, isize, usize, 100_000);
+    test_sequence!(test_i128_sequence, i128, u128, 100_000);
+
+    macro_rules! test_domain {
+        ($name:ident, $type:ident, [$($domain:literal),+]) => {
+            #[test]
+            fn $name() {
+                for domain in [$($domain),+] {
+                    for (seed_base, seed_offset) in [(0, 0), (1, 2), ($type::MAX, $type::MAX / 3)] {
+                        let config = RandomSequenceBuilder::<$type>::new(seed_base, seed_offset).with_domain(domain);
+                        let sequence = config.into_iter();
+
+                        // check every value in the domain appears exactly once, and the iterator is the exact length
+                        assert_eq!(sequence.size_hint(), (domain as usize, Some(domain as usize)));
+                        let nums: Vec<$type> = sequence.clone().collect();
+                        assert_eq!(nums.len(), domain as usize);
+                        let unique: HashSet<$type> = nums.iter().copied().collect();
+                        assert_eq!(unique.len(), domain as usize, "Duplicate values for domain {}", domain);
+                        assert!(nums.iter().all(|num| *num < domain), "Values out of range for domain {}", domain);
+
+                        // check n, index_of and iteration agree
+                        for (i, num) in nums.iter().enumerate() {
+                            assert_eq!(sequence.n(i as $type), *num);
+                            assert_eq!(sequence.index_of(*num), i as $type);
+                        }
+
+                        // check the index wraps around the domain
+                        assert_eq!(sequence.n(domain), nums[0]);
+                        let mut wrapping = sequence.clone();
+                        wrapping.set_index(domain - 1);
+                        assert_eq!(wrapping.wrapping_next(), nums[domain as usize - 1]);
+                        assert_eq!(wrapping.wrapping_next(), nums[0]);
+                        assert_eq!(wrapping.wrapping_prev(), nums[0]);
+                        assert_eq!(wrapping.wrapping_prev(), nums[domain as usize - 1]);
+
+                        // check exhaustion
+                        let mut sequence = sequence;
+                        sequence.set_index(domain - 1);
+                        assert_eq!(sequence.next(), Some(nums[domain as usize - 1]));
+                        assert!(sequence.exhausted());
+                        assert_eq!(sequence.size_hint(), (0, Some(0)));
+                        assert_eq!(sequence.next(), None);
+                    }
+                }
+            }
+        };
+    }
+
+    test_domain!(test_u8_domain, u8, [1, 2, 3, 5, 7, 100, 128, 129, 250, 251, 252, 255]);
+    test_domain!(test_u16_domain, u16, [1, 3, 1000, 4097, 65000, 65519, 65535]);
+    test_domain!(test_u32_domain, u32, [1, 2, 17, 1_000_003]);
+    test_domain!(test_u64_domain, u64, [1, 6, 99_999, 131_073]);
+    test_domain!(test_usize_domain, usize, [1, 4, 100_003]);
+    test_domain!(test_u128_domain, u128, [1, 5, 100_003]);
+
+    #[test]
+    #[should_panic]
+    fn test_empty_domain() {
+        RandomSequenceBuilder::<u32>::new(0, 0).with_domain(0);
+    }
+
+    macro_rules! test_range {
+        ($name:ident, $type:ident, [$(($start:expr, $end:expr, $step:expr)),+]) => {
+            #[test]
+            fn $name() {
+                for (start, end, step) in [$(($start, $end, $step)),+] {
+                    let config = RandomSequenceBuilder::<$type>::new(3, 5).with_range_step(start..=end, step);
+                    let sequence = config.into_iter();
+
+                    // check the sequence covers exactly the values of the stepped range
+                    let expected: HashSet<$type> = (start..=end).step_by(step as usize).collect();
+                    assert_eq!(sequence.size_hint(), (expected.len(), Some(expected.len())));
+                    let nums: Vec<$type> = sequence.clone().collect();
+                    assert_eq!(nums.len(), expected.len());
+                    assert_eq!(nums.iter().copied().collect::<HashSet<$type>>(), expected);
+
+                    // check n, index_of, contains and iteration agree
+                    for (i, num) in nums.iter().enumerate() {
+                        assert_eq!(sequence.n(i as $type), *num);
+                        assert_eq!(sequence.index_of(*num), i as $type);
+                        assert!(sequence.contains(*num));
+                    }
+                    if let Some(before) = start.checked_sub(1) {
+                        assert!(!sequence.contains(before));
+                    }
+                    if let Some(after) = end.checked_add(1) {
+                        assert!(!sequence.contains(after));
+                    }
+                    if step > 1 {
+                        assert!(!sequence.contains(start + 1));
+                    }
+
+                    // check reverse iteration from the end of the range
+                    let mut sequence = sequence;
+                    sequence.set_index(nums.len() as $type - 1);
+                    let reversed: Vec<$type> = sequence.rev().collect();
+                    assert!(reversed.iter().eq(nums.iter().rev().skip(1)));
+                }
+
+                // check the full range is the same sequence as the unmapped type
+                let sequence = RandomSequenceBuilder::<$type>::new(3, 5).with_range(0..=$type::MAX).into_iter();
+                let unmapped = RandomSequenceBuilder::<$type>::new(3, 5).into_iter();
+                assert!(std::iter::zip(sequence, unmapped).take(1000).all(|(a, b)| a == b));
+            }
+        };
+    }
+
+    test_range!(test_u8_range, u8, [(0, 255, 2), (10, 100, 7), (200, 255, 1), (255, 255, 1), (1, 254, 253)]);
+    test_range!(test_u16_range, u16, [(49152, 65535, 1), (0, 65535, 3), (100, 100, 5)]);
+    test_range!(test_u32_range, u32, [(0x0a00_0000, 0x0a00_ffff, 8), (7, 1000, 1)]);
+    test_range!(test_u64_range, u64, [(1 << 40, (1 << 40) + 99_999, 1), (u64::MAX - 100_000, u64::MAX, 4)]);
+    test_range!(test_u128_range, u128, [(1 << 100, (1 << 100) + 99_999, 1), (u128::MAX - 100_000, u128::MAX, 4)]);
+
+    #[test]
+    #[should_panic]
+    fn test_empty_range() {
+        #[allow(clippy::reversed_empty_ranges)]
+        RandomSequenceBuilder::<u32>::new(0, 0).with_range(10..=9);
+    }
+
+    macro_rules! test_signed_range {
+        ($name:ident, $type:ident, $unsigned:ident, [$(($start:expr, $end:expr, $step:expr)),+]) => {
+            #[test]
+            fn $name() {
+                for (start, end, step) in [$(($start, $end, $step)),+] {
+                    let config = RandomSequenceBuilder::<$unsigned>::new(3, 5).with_signed_range_step(start..=end, step);
+                    let sequence = RandomSequence::<$type>::from(config);
+
+                    // check the sequence covers exactly the values of the stepped range
+                    let expected: HashSet<$type> = (start..=end).step_by(step as usize).collect();
+                    let nums: Vec<$type> = sequence.clone().collect();
+                    assert_eq!(nums.len(), expected.len());
+                    assert_eq!(nums.iter().copied().collect::<HashSet<$type>>(), expected);
+
+                    // check n, index_of, contains and iteration agree
+                    for (i, num) in nums.iter().enumerate() {
+                        assert_eq!(sequence.n(i as $type), *num);
+                        assert_eq!(sequence.index_of(*num), i as $type);
+                        assert!(sequence.contains(*num));
+                    }
+                    if let Some(before) = start.checked_sub(1) {
+                        assert!(!sequence.contains(before));
+                    }
+                    if let Some(after) = end.checked_add(1) {
+                        assert!(!sequence.contains(after));
+                    }
+                }
+            }
+        };
+    }
+
+    test_signed_range!(test_i8_range, i8, u8, [(-128, 127, 1), (-100, 100, 3), (-5, -1, 1)]);
+    test_signed_range!(test_i16_range, i16, u16, [(-1000, 1000, 1), (i16::MIN, i16::MAX, 7)]);
+    test_signed_range!(test_i32_range, i32, u32, [(-1000, 1000, 1), (i32::MIN, i32::MIN + 100_000, 2)]);
+    test_signed_range!(test_i64_range, i64, u64, [(-50_000, 50_000, 1), (i64::MAX - 1000, i64::MAX, 10)]);
+    test_signed_range!(test_i128_range, i128, u128, [(-50_000, 50_000, 1)]);
+
+    macro_rules! test_feistel {
+        ($name:ident, $type:ident, [$($domain:expr),+]) => {
+            #[test]
+            fn $name() {
+                let backend = Backend::Feistel { rounds: 8 };
+                let config = RandomSequenceBuilder::<$type>::new(7, 11).with_backend(backend);
+                let sequence = config.into_iter();
+
+                // check the feistel sequence differs from the default sequence
+                let default_sequence = RandomSequenceBuilder::<$type>::new(7, 11).into_iter();
+                assert!((0..100).any(|i| sequence.n(i) != default_sequence.n(i)));
+
+                // check n, index_of and uniqueness across the first values
+                let nums: Vec<$type> = sequence.clone().take(65536).collect();
+                assert_eq!(nums.iter().copied().collect::<HashSet<$type>>().len(), nums.len());
+                for (i, num) in nums.iter().enumerate().take(1000) {
+                    assert_eq!(sequence.n(i as $type), *num);
+                    assert_eq!(sequence.index_of(*num), i as $type);
+                }
+
+                // check the feistel backend over awkward domains
+                for domain in [$($domain),+] {
+                    let sequence = RandomSequenceBuilder::<$type>::new(7, 11).with_backend(backend).with_domain(domain).into_iter();
+                    let nums: Vec<$type> = sequence.clone().collect();
+                    assert_eq!(nums.len(), domain as usize);
+                    assert_eq!(nums.iter().copied().collect::<HashSet<$type>>().len(), domain as usize);
+                    for (i, num) in nums.iter().enumerate() {
+                        assert!(*num < domain);
+                        assert_eq!(sequence.index_of(*num), i as $type);
+                    }
+                }
+            }
+        };
+    }
+
+    test_feistel!(test_u8_feistel, u8, [1, 2, 3, 100, 129, 255]);
+    test_feistel!(test_u16_feistel, u16, [5, 1000, 65535]);
+    test_feistel!(test_u32_feistel, u32, [7, 100_003]);
+    test_feistel!(test_u64_feistel, u64, [9, 10_007]);
+    test_feistel!(test_u128_feistel, u128, [11, 10_007]);
+
+    #[test]
+    fn test_legacy_key_schedule_output() {
+        // the sequences generated before the round count, key schedule and tail folding were configurable,
+        // which the default builders keep generating
+        let sequence = RandomSequenceBuilder::<u8>::new(7, 11).into_iter();
+        assert_eq!((0..6).map(|i| sequence.n(i)).collect::<Vec<_>>(), [119, 120, 81, 164, 199, 33]);
+        let sequence = RandomSequenceBuilder::<u16>::new(7, 11).into_iter();
+        assert_eq!((0..6).map(|i| sequence.n(i)).collect::<Vec<_>>(), [7613, 52271, 9151, 24109, 10654, 50144]);
+        let sequence = RandomSequenceBuilder::<u32>::new(7, 11).into_iter();
+        assert_eq!(
+            (0..6).map(|i| sequence.n(i)).collect::<Vec<_>>(),
+            [3071266996, 1887725396, 2319257821, 4262881713, 927733140, 3178534268],
+        );
+        let sequence = RandomSequenceBuilder::<u64>::new(7, 11).into_iter();
+        assert_eq!(
+            (0..3).map(|i| sequence.n(i)).collect::<Vec<_>>(),
+            [5686466711797331178, 12731298035047074148, 16093546932401586640],
+        );
+        let sequence = RandomSequenceBuilder::<u128>::new(7, 11).into_iter();
+        assert_eq!(
+            (0..2).map(|i| sequence.n(i)).collect::<Vec<_>>(),
+            [68715916246727507435124729298539492172, 195846664981735963085338085385113166052],
+        );
+        let sequence = RandomSequenceBuilder::<u32>::new(7, 11).with_domain(1000).into_iter();
+        assert_eq!((0..6).map(|i| sequence.n(i)).collect::<Vec<_>>(), [4, 567, 665, 767, 978, 234]);
+    }
+
+    macro_rules! test_key_schedule {
+        ($name:ident, $type:ident, [$($domain:expr),+]) => {
+            #[test]
+            fn $name() {
+                // the offset of sequence `b` within sequence `a`, if `b` is a rotation of `a`
+                let rotation = |a: &RandomSequence<$type>, b: &RandomSequence<$type>| {
+                    let shift = a.index_of(b.n(0));
+                    (0..100).all(|i: $type| b.n(i) == a.n(i.wrapping_add(shift)))
+                };
+
+                // legacy builders differing only by seed_base are rotations of the same cycle
+                let legacy = |seed_base| RandomSequenceBuilder::<$type>::new(seed_base, 11).into_iter();
+                assert!(rotation(&legacy(7), &legacy(8)));
+
+                // seeded builders are distinct permutations for every seed
+                let seeded = |seed_base, seed_offset| {
+                    RandomSequenceBuilder::<$type>::new(seed_base, seed_offset).with_key_schedule(KeySchedule::Seeded).into_iter()
+                };
+                assert!(!rotation(&seeded(7, 11), &seeded(8, 11)));
+                assert!(!rotation(&seeded(7, 11), &seeded(7, 12)));
+                assert!(!rotation(&seeded(0, 0), &seeded(1, 0)));
+
+                // check the seeded schedule is invertible with any rounds and domains
+                for seed_base in [0, 1, 7] {
+                    for rounds in [1, 2, 3] {
+                        let config = RandomSequenceBuilder::<$type>::new(seed_base, 11).with_key_schedule(KeySchedule::Seeded);
+                        let sequence = config.with_rounds(rounds).into_iter();
+                        for (i, num) in sequence.clone().take(1000).enumerate() {
+                            assert_eq!(sequence.index_of(num), i as $type);
+                        }
+
+                        for domain in [$($domain),+] {
+                            let sequence = config.with_rounds(rounds).with_domain(domain).into_iter();
+                            let nums: Vec<$type> = sequence.clone().collect();
+                            assert_eq!(nums.iter().copied().collect::<HashSet<$type>>().len(), domain as usize);
+                            for (i, num) in nums.iter().enumerate() {
+                                assert!(*num < domain);
+                                assert_eq!(sequence.index_of(*num), i as $type);
+                            }
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    test_key_schedule!(test_u8_key_schedule, u8, [1, 3, 100, 255]);
+    test_key_schedule!(test_u16_key_schedule, u16, [5, 1000]);
+    test_key_schedule!(test_u32_key_schedule, u32, [7, 1000]);
+    test_key_schedule!(test_u64_key_schedule, u64, [9, 1000]);
+    test_key_schedule!(test_u128_key_schedule, u128, [11, 1000]);
+
+    macro_rules! test_rounds {
+        ($name:ident, $type:ident, $domain:expr) => {
+            #[test]
+            fn $name() {
+                let default_sequence = RandomSequenceBuilder::<$type>::new(7, 11).into_iter();
+                for rounds in [1, 2, 3, 4, 8] {
+                    let config = RandomSequenceBuilder::<$type>::new(7, 11).with_rounds(rounds);
+                    let sequence = config.into_iter();
+                    match rounds {
+                        2 => assert!((0..100).all(|i| sequence.n(i) == default_sequence.n(i))),
+                        _ => assert!((0..100).any(|i| sequence.n(i) != default_sequence.n(i))),
+                    }
+
+                    // check n, index_of and uniqueness across the first values
+                    let nums: Vec<$type> = sequence.clone().take(65536).collect();
+                    assert_eq!(nums.iter().copied().collect::<HashSet<$type>>().len(), nums.len());
+                    for (i, num) in nums.iter().enumerate().take(1000) {
+                        assert_eq!(sequence.index_of(*num), i as $type);
+                    }
+
+                    // check the rounds over a domain
+                    let sequence = config.with_domain($domain).into_iter();
+                    let nums: Vec<$type> = sequence.clone().collect();
+                    assert_eq!(nums.iter().copied().collect::<HashSet<$type>>().len(), $domain as usize);
+                    for (i, num) in nums.iter().enumerate() {
+                        assert!(*num < $domain);
+                        assert_eq!(sequence.index_of(*num), i as $type);
+                    }
+                }
+            }
+        };
+    }
+
+    test_rounds!(test_u8_rounds, u8, 100);
+    test_rounds!(test_u16_rounds, u16, 1000);
+    test_rounds!(test_u32_rounds, u32, 1000);
+    test_rounds!(test_u64_rounds, u64, 1000);
+    test_rounds!(test_u128_rounds, u128, 1000);
+
+    #[test]
+    #[should_panic]
+    fn test_zero_rounds() {
+        RandomSequenceBuilder::<u32>::new(0, 0).with_rounds(0);
+    }
+
+    #[cfg(feature = "ff1")]
+    macro_rules! test_ff1 {
+        ($name:ident, $type:ident, [$($domain:expr),+]) => {
+            #[test]
+            fn $name() {
+                let key = crate::Ff1Key::Aes256([0x2b; 32]);
+                let sequence = crate::Ff1Sequence::<$type>::new(key.clone());
+
+                // check the sequence depends on the key
+                let other_key = crate::Ff1Sequence::<$type>::new(crate::Ff1Key::Aes128([0x2b; 16]));
+                assert!((0..100).any(|i| sequence.n(i) != other_key.n(i)));
+
+                // check n, index_of and uniqueness across the first values
+                let nums: Vec<$type> = sequence.clone().take(4096).collect();
+                assert_eq!(nums.iter().copied().collect::<HashSet<$type>>().len(), nums.len());
+                for (i, num) in nums.iter().enumerate().take(1000) {
+                    assert_eq!(sequence.n(i as $type), *num);
+                    assert_eq!(sequence.index_of(*num), i as $type);
+                }
+
+                // check the ff1 backend over awkward domains
+                for domain in [$($domain),+] {
+                    let sequence = crate::Ff1Builder::<$type>::new(key.clone()).with_domain(domain).into_iter();
+                    let nums: Vec<$type> = sequence.clone().collect();
+                    assert_eq!(nums.len(), domain as usize);
+                    assert_eq!(nums.iter().copied().collect::<HashSet<$type>>().len(), domain as usize);
+                    for (i, num) in nums.iter().enumerate() {
+                        assert!(*num < domain);
+                        assert_eq!(sequence.index_of(*num), i as $type);
+                    }
+                }
+            }
+        };
+    }
+
+    #[cfg(feature = "ff1")]
+    test_ff1!(test_u8_ff1, u8, [1, 2, 3, 100, 255]);
+    #[cfg(feature = "ff1")]
+    test_ff1!(test_u16_ff1, u16, [5, 1000]);
+    #[cfg(feature = "ff1")]
+    test_ff1!(test_u32_ff1, u32, [7, 10_007]);
+    #[cfg(feature = "ff1")]
+    test_ff1!(test_u64_ff1, u64, [9, 10_007]);
+    #[cfg(feature = "ff1")]
+    test_ff1!(test_u128_ff1, u128, [11, 10_007]);
+
+    /// The affine permutation `5 * index + 3` over every u8, inverted by the multiplicative inverse.
+    #[derive(Clone)]
+    struct Affine;
+
+    impl Permutation<u8> for Affine {
+        fn apply(&self, index: u8) -> u8 {
+            index.wrapping_mul(5).wrapping_add(3)
+        }
+
+        fn invert(&self, value: u8) -> Option<u8> {
+            Some(value.wrapping_sub(3).wrapping_mul(205))
+        }
+    }
+
+    /// The values `0..10` in reverse, without an inverse.
+    #[derive(Clone)]
+    struct Reverse;
+
+    impl Permutation<i16> for Reverse {
+        fn max_index(&self) -> u16 {
+            9
+        }
+
+        fn apply(&self, index: u16) -> i16 {
+            9 - index as i16
+        }
+
+        fn contains(&self, value: i16) -> bool {
+            (0..10).contains(&value)
+        }
+    }
+
+    #[test]
+    fn test_custom_permutation() {
+        let mut sequence = RandomSequence::from_permutation(Affine);
+        assert_eq!(sequence.len(), 256);
+        let nums: Vec<u8> = sequence.clone().collect();
+        assert_eq!(nums.iter().copied().collect::<HashSet<u8>>().len(), 256);
+        for (i, num) in nums.iter().enumerate() {
+            assert_eq!(sequence.n(i as u8), *num);
+            assert_eq!(sequence.index_of(*num), i as u8);
+        }
+        assert_eq!(sequence.wrapping_prev(), nums[255]);
+        assert_eq!(sequence.wrapping_next(), nums[255]);
+        assert_eq!(sequence.wrapping_next(), nums[0]);
+
+        let mut sequence = RandomSequence::from_permutation(Reverse);
+        assert_eq!(sequence.len(), 10);
+        assert_eq!(sequence.clone().collect::<Vec<i16>>(), (0..10).rev().collect::<Vec<i16>>());
+        assert_eq!(sequence.n(12), 7);
+        assert!(sequence.contains(3));
+        assert!(!sequence.contains(10));
+
+        // the cursor wraps around the custom max_index
+        sequence.set_index(12);
+        assert_eq!(sequence.index(), Some(2));
+        assert_eq!(sequence.next(), Some(7));
+        assert_eq!(sequence.wrapping_prev(), 7);
+        assert_eq!(sequence.wrapping_prev(), 8);
+        sequence.set_index(9);
+        assert_eq!(sequence.next(), Some(0));
+        assert_eq!(sequence.next(), None);
+        assert!(sequence.exhausted());
+        assert_eq!(sequence.wrapping_next(), 0);
+        assert_eq!(sequence.wrapping_next(), 9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_custom_permutation_without_invert() {
+        RandomSequence::from_permutation(Reverse).index_of(3);
+    }
 
     macro_rules! test_exact_size_iterator {
         ($name:ident, $type:ident) => {
             #[test]
             fn $name() {
-                let config = RandomSequenceBuilder::<$type>::new(0, 0);
-                let mut sequence = config.clone().into_iter();
-                assert_eq!(sequence.len(), $type::MAX as usize + 1);  // eg. 256 items, but u8::MAX = 255
+                // the largest index, as usize
+                const MAX: usize = (1 << $type::BITS) - 1;
+
+                let fresh_sequence = RandomSequence::<$type>::new(0, 0);
+                let mut sequence = fresh_sequence.clone();
+                assert_eq!(sequence.len(), MAX + 1);  // eg. 256 items, but u8::MAX = 255
                 sequence.next();
-                assert_eq!(sequence.len(), $type::MAX as usize);
+                assert_eq!(sequence.len(), MAX);
                 sequence.next();
-                assert_eq!(sequence.len(), $type::MAX as usize - 1);
+                assert_eq!(sequence.len(), MAX - 1);
                 sequence.next();
-                assert_eq!(sequence.len(), $type::MAX as usize - 2);
+                assert_eq!(sequence.len(), MAX - 2);
                 sequence.prev();
-                assert_eq!(sequence.len(), $type::MAX as usize - 1);
+                assert_eq!(sequence.len(), MAX - 1);
 
                 sequence.next();
                 sequence.next();
                 sequence.next();
-                assert_eq!(sequence.len(), $type::MAX as usize - 4);
+                assert_eq!(sequence.len(), MAX - 4);
 
                 // don't collect a u32, only test this on u8 and u16
                 if sequence.len() <= u16::MAX as usize + 1 {
@@ -308,10 +1036,10 @@ mod tests {
                     assert_eq!(remaining_len, remaining_items.len());
 
                     // double check a fresh sequence
-                    let sequence = config.into_iter();
-                    assert_eq!(sequence.len(), $type::MAX as usize + 1);
+                    let sequence = fresh_sequence;
+                    assert_eq!(sequence.len(), MAX + 1);
                     let all_items: Vec<_> = sequence.collect();
-                    assert_eq!(all_items.len(), $type::MAX as usize + 1);
+                    assert_eq!(all_items.len(), MAX + 1);
                     assert_eq!(all_items.len(), remaining_items.len() + 5);
                 }
             }
@@ -322,6 +1050,247 @@ mod tests {
     test_exact_size_iterator!(test_u16_exact_size_iterator, u16);
     #[cfg(target_pointer_width = "64")]
     test_exact_size_iterator!(test_u32_exact_size_iterator, u32);
+    test_exact_size_iterator!(test_i8_exact_size_iterator, i8);
+    test_exact_size_iterator!(test_i16_exact_size_iterator, i16);
+    #[cfg(target_pointer_width = "64")]
+    test_exact_size_iterator!(test_i32_exact_size_iterator, i32);
+
+    #[test]
+    fn test_unsized_hint() {
+        // 32-bit targets, where u32 sequences don't implement ExactSizeIterator
+        const MAX_32: u128 = u32::MAX as u128;
+        assert_eq!(unsized_hint(u32::MAX as u128, MAX_32), (u32::MAX as usize, None));
+        assert_eq!(unsized_hint(u32::MAX as u128 - 1, MAX_32), (u32::MAX as usize, Some(u32::MAX as usize)));
+        assert_eq!(unsized_hint(999, MAX_32), (1000, Some(1000)));
+        assert_eq!(unsized_hint(0, MAX_32), (1, Some(1)));
+        assert_eq!(unsized_hint(u64::MAX as u128, MAX_32), (u32::MAX as usize, None));
+
+        // the targets of the host
+        const MAX: u128 = usize::MAX as u128;
+        assert_eq!(unsized_hint(usize::MAX as u128, MAX), (usize::MAX, None));
+        assert_eq!(unsized_hint(u128::MAX, MAX), (usize::MAX, None));
+        assert_eq!(unsized_hint(999, MAX), (1000, Some(1000)));
+
+        // the sequences apply the same hints
+        let sequence = RandomSequence::<u64>::new(0, 0);
+        assert_eq!(sequence.size_hint(), unsized_hint(u64::MAX as u128, MAX));
+        let mut sequence = RandomSequenceBuilder::<u64>::new(0, 0).with_domain(1000).into_iter();
+        sequence.next();
+        assert_eq!(sequence.size_hint(), (999, Some(999)));
+        sequence.by_ref().for_each(drop);
+        assert_eq!(sequence.size_hint(), (0, Some(0)));
+        assert_eq!(sequence.remaining(), 0);
+
+        // the remaining count holds the 2^64 elements of a full u64 sequence
+        let mut sequence = RandomSequence::<u64>::new(0, 0);
+        assert_eq!(sequence.remaining(), 1 << 64);
+        sequence.next();
+        assert_eq!(sequence.remaining(), (1 << 64) - 1);
+        sequence.set_index(u64::MAX);
+        assert_eq!(sequence.remaining(), 1);
+        sequence.next();
+        assert_eq!(sequence.remaining(), 0);
+        assert_eq!(RandomSequence::<u128>::new(0, 0).remaining(), u128::MAX);
+        assert_eq!(RandomSequenceBuilder::<u64>::new(0, 0).with_domain(1000).into_iter().remaining(), 1000);
+    }
+
+    /// A sequence with the default `nth`, `nth_back`, `last` and `count` of the iterator traits,
+    /// which walk element by element.
+    #[derive(Clone)]
+    struct Walk<T: SequenceInt>(RandomSequence<T>);
+
+    impl<T: SequenceInt> Iterator for Walk<T> {
+        type Item = T;
+
+        fn next(&mut self) -> Option<T> {
+            self.0.next()
+        }
+    }
+
+    impl<T: SequenceInt> DoubleEndedIterator for Walk<T> {
+        fn next_back(&mut self) -> Option<T> {
+            self.0.prev()
+        }
+    }
+
+    macro_rules! test_nth {
+        ($name:ident, $type:ident, $unsigned:ident) => {
+            #[test]
+            fn $name() {
+                let config = RandomSequenceBuilder::<$unsigned>::new(7, 11);
+                for config in [config, config.with_domain(100), config.with_domain(1)] {
+                    let sequence = RandomSequence::<$type>::from(config);
+                    let max_index = config.max_index();
+                    let starts = [0, 1, 50, max_index.saturating_sub(3), max_index];
+                    for start in starts.into_iter().filter(|start| *start <= max_index) {
+                        for n in [0, 1, 2, 5, 99, 100, 300] {
+                            let mut fast = sequence.clone();
+                            fast.set_index(start as $type);
+                            let mut walk = Walk(fast.clone());
+
+                            // the same values and cursor as walking, forwards, backwards and past either end
+                            for _ in 0..3 {
+                                assert_eq!(fast.nth(n), walk.nth(n), "{:?} from {} by {}", config, start, n);
+                                assert_eq!((fast.index(), fast.exhausted()), (walk.0.index(), walk.0.exhausted()));
+                                assert_eq!((fast.peek(), fast.peek_back()), (walk.0.clone().next(), walk.0.clone().prev()));
+                            }
+                            for _ in 0..3 {
+                                assert_eq!(fast.nth_back(n), walk.nth_back(n), "{:?} from {} by {}", config, start, n);
+                                assert_eq!((fast.index(), fast.exhausted()), (walk.0.index(), walk.0.exhausted()));
+                                assert_eq!((fast.peek(), fast.peek_back()), (walk.0.clone().next(), walk.0.clone().prev()));
+                            }
+
+                            // skip walks from the start of the sequence, and step_by walks the rest
+                            let skip = (start as usize).min(1000);
+                            let (fast, walk) = (sequence.clone().skip(skip), Walk(sequence.clone()).skip(skip));
+                            assert!(fast.clone().take(5).eq(walk.clone().take(5)));
+                            if ((max_index - start) as u128) < 1000 {
+                                let mut fast = sequence.clone();
+                                fast.set_index(start as $type);
+                                assert!(fast.clone().step_by(n + 1).eq(Walk(fast).step_by(n + 1)));
+                            } else {
+                                assert!(fast.step_by(n + 1).take(5).eq(walk.step_by(n + 1).take(5)));
+                            }
+                        }
+
+                        // last and count, where walking the rest of the sequence is feasible
+                        let mut fast = sequence.clone();
+                        fast.set_index(start as $type);
+                        if ((max_index - start) as u128) < 1000 {
+                            assert_eq!(fast.clone().last(), Walk(fast.clone()).last());
+                            assert_eq!(fast.clone().count(), Walk(fast.clone()).count());
+                        }
+                        assert_eq!(fast.clone().last(), Some(sequence.n(max_index as $type)));
+                        fast.set_index(max_index as $type);
+                        assert_eq!(fast.nth(1), None);
+                        assert!(fast.exhausted());
+                        assert_eq!((fast.clone().last(), fast.clone().count(), fast.peek()), (None, 0, None));
+                    }
+                }
+
+                // jump far into the full sequence
+                let mut sequence = RandomSequence::<$type>::new(7, 11);
+                let far = ($unsigned::MAX / 3) as usize;
+                assert_eq!(sequence.nth(far), Some(sequence.n(far as $type)));
+                assert_eq!(sequence.peek(), Some(sequence.n(far as $type + 1)));
+                assert_eq!(sequence.nth_back(far / 2), Some(sequence.n((far - far / 2) as $type)));
+                let values: Vec<$type> = sequence.clone().step_by(far).take(3).collect();
+                assert_eq!(values[1], sequence.n((far - far / 2 + far) as $type));
+            }
+        };
+    }
+
+    test_nth!(test_u8_nth, u8, u8);
+    test_nth!(test_u16_nth, u16, u16);
+    test_nth!(test_u32_nth, u32, u32);
+    test_nth!(test_u64_nth, u64, u64);
+    test_nth!(test_u128_nth, u128, u128);
+    test_nth!(test_i8_nth, i8, u8);
+    test_nth!(test_i64_nth, i64, u64);
+
+    #[test]
+    #[should_panic(expected = "doesn't fit in a usize")]
+    fn test_count_overflow() {
+        RandomSequence::<u64>::new(0, 0).count();
+    }
+
+    macro_rules! test_many {
+        ($name:ident, $type:ident, $unsigned:ident) => {
+            #[test]
+            fn $name() {
+                // every configuration matches n, including the scalar backends and batch remainders
+                for config in test_configs::<$unsigned>() {
+                    let sequence = RandomSequence::<$type>::from(config);
+                    for len in [0, 1, 3, 64, 65, 200] {
+                        let start = (0 as $type).wrapping_sub(50);
+                        let mut values = std::vec![0 as $type; len];
+                        sequence.fill(&mut values, start);
+                        let mut wrapping = sequence.clone();
+                        wrapping.set_index(start);
+                        let expected: Vec<$type> = (0..len).map(|_| wrapping.wrapping_next()).collect();
+                        assert_eq!(values, expected, "{:?}", config);
+
+                        let indices: Vec<$type> = (0..len).map(|i| (i as $type).wrapping_mul(97)).collect();
+                        sequence.n_many(&indices, &mut values);
+                        let expected: Vec<$type> = indices.iter().map(|i| sequence.n(*i)).collect();
+                        assert_eq!(values, expected, "{:?}", config);
+                    }
+                }
+            }
+        };
+    }
+
+    test_many!(test_u8_many, u8, u8);
+    test_many!(test_u16_many, u16, u16);
+    test_many!(test_u32_many, u32, u32);
+    test_many!(test_u64_many, u64, u64);
+    test_many!(test_u128_many, u128, u128);
+    test_many!(test_i32_many, i32, u32);
+
+    #[test]
+    #[should_panic(expected = "must have the same length")]
+    fn test_many_length() {
+        RandomSequence::<u32>::new(0, 0).n_many(&[1, 2], &mut [0]);
+    }
+
+    macro_rules! test_sequential {
+        ($name:ident, $type:ident, $unsigned:ident) => {
+            #[test]
+            fn $name() {
+                // the incremental square of each walk matches n, across the prime, the wrap of the
+                // absolute index, the end of the sequence and changes of direction
+                for config in test_configs::<$unsigned>() {
+                    let sequence = RandomSequence::<$type>::from(config);
+                    let (max_index, keys) = (config.max_index(), config.keys());
+                    let step = |index: $unsigned, forward: bool| match (forward, index) {
+                        (true, index) if index == max_index => 0,
+                        (true, index) => index + 1,
+                        (false, 0) => max_index,
+                        (false, index) => index - 1,
+                    };
+                    let starts = [
+                        0,
+                        max_index.saturating_sub(5),
+                        config.sub_index((config.prime - 1).min(max_index), keys.start_index).saturating_sub(5),
+                        config.sub_index(0, keys.start_index).saturating_sub(5),
+                    ];
+                    for start in starts {
+                        let mut walk = sequence.clone();
+                        walk.set_index(start as $type);
+                        let mut index = start;
+                        for i in 0..40 {
+                            let forward = i % 13 < 9;
+                            if forward {
+                                assert_eq!(walk.wrapping_next(), sequence.n(index as $type), "{:?} at {}", config, index);
+                                index = step(index, true);
+                            } else {
+                                index = step(index, false);
+                                assert_eq!(walk.wrapping_prev(), sequence.n(index as $type), "{:?} at {}", config, index);
+                            }
+                        }
+                    }
+
+                    // the iterators in both directions
+                    let mut walk = sequence.clone();
+                    walk.set_index(max_index.saturating_sub(20) as $type);
+                    let values: Vec<$type> = walk.clone().collect();
+                    let expected: Vec<$type> = (max_index.saturating_sub(20)..=max_index).map(|i| sequence.n(i as $type)).collect();
+                    assert_eq!(values, expected, "{:?}", config);
+                    walk.set_index(20.min(max_index) as $type);
+                    let values: Vec<$type> = std::iter::from_fn(|| walk.prev()).collect();
+                    let expected: Vec<$type> = (0..20.min(max_index)).rev().map(|i| sequence.n(i as $type)).collect();
+                    assert_eq!(values, expected, "{:?}", config);
+                }
+            }
+        };
+    }
+
+    test_sequential!(test_u8_sequential, u8, u8);
+    test_sequential!(test_u16_sequential, u16, u16);
+    test_sequential!(test_u32_sequential, u32, u32);
+    test_sequential!(test_u64_sequential, u64, u64);
+    test_sequential!(test_u128_sequential, u128, u128);
+    test_sequential!(test_i32_sequential, i32, u32);
 
     macro_rules! test_distribution {
         ($name:ident, $type:ident, $check:literal) => {
@@ -374,4 +1343,5 @@ mod tests {
     test_distribution!(test_u32_distribution, u32, 100_000);
     test_distribution!(test_u64_distribution, u64, 100_000);
     test_distribution!(test_usize_distribution, usize, 100_000);
+    test_distribution!(test_u128_distribution, u128, 100_000);
 }
@@ -0,0 +1,573 @@
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use num_traits::{FromPrimitive, One, Zero};
+
+use crate::builder::to_u128;
+use crate::permutation::{BuilderPermutation, Permutation};
+use crate::sequence::{unsized_hint, RandomSequence};
+use crate::signed::SequenceInt;
+
+/// One of `n` disjoint shards of a [RandomSequence], built by [RandomSequence::shard] or
+/// [RandomSequence::shard_interleaved], which together cover every index of the sequence once.
+///
+/// A shard is a double-ended iterator over the values of its indices, with its own copy of the
+/// permutation, so each worker can iterate its shard independently of the others. It is an
+/// `ExactSizeIterator` for the same types as a [RandomSequence], as a single shard of a `u64`
+/// sequence holds all `2^64` values, and otherwise reports its length with [Shard::remaining].
+///
+/// ```rust
+/// use rand_unique::{RandomSequence, RandomSequenceBuilder};
+///
+/// let sequence: RandomSequence<u16> = RandomSequenceBuilder::<u16>::new(10, 2).into_iter();
+/// let mut shard = sequence.shard(1, 4);
+/// assert_eq!(shard.len(), 16384);
+/// assert_eq!(shard.index(), Some(16384));
+/// assert_eq!(shard.next(), Some(sequence.n(16384)));
+/// assert_eq!(shard.next_back(), Some(sequence.n(32767)));
+///
+/// let mut shard = sequence.shard_interleaved(1, 4);
+/// assert_eq!(shard.next(), Some(sequence.n(1)));
+/// assert_eq!(shard.next(), Some(sequence.n(5)));
+/// assert_eq!(shard.index(), Some(9));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Shard<T, P = BuilderPermutation<<T as SequenceInt>::Unsigned>>
+where
+    T: SequenceInt
+{
+    permutation: P,
+
+    /// The next index from the front.
+    front: T::Unsigned,
+
+    /// The next index from the back.
+    back: T::Unsigned,
+
+    /// The distance between the indices of the shard, 1 for a contiguous shard.
+    step: T::Unsigned,
+
+    /// The end marker, once the front has passed the back.
+    ended: bool,
+}
+
+/// An iterator over the `n` disjoint shards of a [RandomSequence], built by
+/// [RandomSequence::shards] or [RandomSequence::shards_interleaved].
+#[derive(Debug, Clone)]
+pub struct Shards<T, P = BuilderPermutation<<T as SequenceInt>::Unsigned>>
+where
+    T: SequenceInt
+{
+    permutation: P,
+
+    /// The number of shards.
+    n: usize,
+
+    /// The shards left to yield.
+    shards: Range<usize>,
+
+    /// Whether the shards are interleaved, rather than contiguous blocks.
+    interleaved: bool,
+
+    /// The type of the values of the shards.
+    values: PhantomData<T>,
+}
+
+impl<T, P> RandomSequence<T, P>
+where
+    T: SequenceInt,
+    P: Permutation<T> + Clone,
+{
+    /// Shard `k` of `n` contiguous blocks of indices, which together cover the sequence with no
+    /// overlap, such as for `n` workers. The blocks differ in length by at most one, with the
+    /// longer blocks first. See [RandomSequence::shard_interleaved] for shards of the indices
+    /// `k mod n` instead.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero, or `k` isn't below `n`.
+    pub fn shard(&self, k: usize, n: usize) -> Shard<T, P> {
+        Shard::contiguous(self.permutation.clone(), k, n)
+    }
+
+    /// Shard `k` of `n` interleaved shards, the indices `k`, `k + n`, `k + 2n` and so on, which
+    /// together cover the sequence with no overlap, such as for `n` workers. Each shard spreads
+    /// over the whole sequence, so the shards progress through it together.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero, or `k` isn't below `n`.
+    pub fn shard_interleaved(&self, k: usize, n: usize) -> Shard<T, P> {
+        Shard::interleaved(self.permutation.clone(), k, n)
+    }
+
+    /// Iterate over the `n` contiguous shards of the sequence, see [RandomSequence::shard], or
+    /// [RandomSequence::shards_interleaved] for the interleaved shards.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero.
+    pub fn shards(&self, n: usize) -> Shards<T, P> {
+        assert!(n > 0, "The number of shards must be positive.");
+        Shards {
+            permutation: self.permutation.clone(),
+            n,
+            shards: 0..n,
+            interleaved: false,
+            values: PhantomData,
+        }
+    }
+
+    /// Iterate over the `n` interleaved shards of the sequence, see
+    /// [RandomSequence::shard_interleaved].
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero.
+    pub fn shards_interleaved(&self, n: usize) -> Shards<T, P> {
+        Shards {
+            interleaved: true,
+            ..self.shards(n)
+        }
+    }
+}
+
+impl<T, P> Shard<T, P>
+where
+    T: SequenceInt,
+    P: Permutation<T>,
+{
+    /// See [RandomSequence::shard].
+    fn contiguous(permutation: P, k: usize, n: usize) -> Self {
+        assert!(k < n, "The shard must be below the number of shards.");
+        let max_index = permutation.max_index();
+        let Some(shards) = T::Unsigned::from_usize(n) else {
+            // more shards than indices, so each index is a shard of its own
+            return Self::single(permutation, k);
+        };
+        if shards.is_one() {
+            return Self::new(permutation, T::Unsigned::zero(), max_index, T::Unsigned::one());
+        }
+
+        // max_index + 1 = base * n + extra, without overflowing for a full sequence
+        let (quotient, rest) = (max_index / shards, max_index % shards);
+        let (base, extra) = match rest + T::Unsigned::one() == shards {
+            true => (quotient + T::Unsigned::one(), T::Unsigned::zero()),
+            false => (quotient, rest + T::Unsigned::one()),
+        };
+        let k = T::Unsigned::from_usize(k).expect("The shard is below the number of shards.");
+        let (start, len) = match k < extra {
+            true => (k * (base + T::Unsigned::one()), base + T::Unsigned::one()),
+            false => (k * base + extra, base),
+        };
+        match len.is_zero() {
+            true => Self::empty(permutation),
+            false => Self::new(permutation, start, start + (len - T::Unsigned::one()), T::Unsigned::one()),
+        }
+    }
+
+    /// See [RandomSequence::shard_interleaved].
+    fn interleaved(permutation: P, k: usize, n: usize) -> Self {
+        assert!(k < n, "The shard must be below the number of shards.");
+        let max_index = permutation.max_index();
+        let Some(shards) = T::Unsigned::from_usize(n) else {
+            return Self::single(permutation, k);
+        };
+
+        let k = T::Unsigned::from_usize(k).expect("The shard is below the number of shards.");
+        match k <= max_index {
+            true => Self::new(permutation, k, k + (max_index - k) / shards * shards, shards),
+            false => Self::empty(permutation),
+        }
+    }
+
+    /// The shard of the single index `k`, when there are more shards than indices.
+    #[inline]
+    fn single(permutation: P, k: usize) -> Self {
+        match T::Unsigned::from_usize(k).filter(|k| *k <= permutation.max_index()) {
+            Some(k) => Self::new(permutation, k, k, T::Unsigned::one()),
+            None => Self::empty(permutation),
+        }
+    }
+
+    /// A shard without any indices.
+    #[inline]
+    fn empty(permutation: P) -> Self {
+        Shard {
+            ended: true,
+            ..Self::new(permutation, T::Unsigned::zero(), T::Unsigned::zero(), T::Unsigned::one())
+        }
+    }
+
+    /// The shard of the indices `front..=back` a multiple of `step` apart.
+    #[inline]
+    fn new(permutation: P, front: T::Unsigned, back: T::Unsigned, step: T::Unsigned) -> Self {
+        Shard {
+            permutation,
+            front,
+            back,
+            step,
+            ended: false,
+        }
+    }
+
+    /// Get the index in the sequence of the next value from the front of the shard. Will return
+    /// `None` if the shard has been exhausted.
+    #[inline]
+    pub fn index(&self) -> Option<T> {
+        match self.ended {
+            false => Some(T::from_unsigned(self.front)),
+            true => None,
+        }
+    }
+
+    /// Check if this shard has been exhausted.
+    #[inline]
+    pub fn exhausted(&self) -> bool {
+        self.ended
+    }
+
+    /// The number of values left in the shard, see [RandomSequence::remaining].
+    ///
+    /// Saturates at `u128::MAX` for a single shard of a full `u128` sequence.
+    #[inline]
+    pub fn remaining(&self) -> u128 {
+        match self.ended {
+            false => to_u128(self.steps_left()).saturating_add(1),
+            true => 0,
+        }
+    }
+
+    /// The number of steps from the front to the back, one less than the values left.
+    #[inline]
+    fn steps_left(&self) -> T::Unsigned {
+        (self.back - self.front) / self.step
+    }
+
+    /// Get the next value from the front.
+    #[inline]
+    fn next_front(&mut self) -> Option<T> {
+        if self.ended {
+            return None;
+        }
+        let value = self.permutation.apply_sequential(self.front);
+        match self.front == self.back {
+            true => self.ended = true,
+            false => self.front = self.front + self.step,
+        }
+        Some(value)
+    }
+
+    /// Get the next value from the back.
+    #[inline]
+    fn next_back_value(&mut self) -> Option<T> {
+        if self.ended {
+            return None;
+        }
+        let value = self.permutation.apply_sequential(self.back);
+        match self.front == self.back {
+            true => self.ended = true,
+            false => self.back = self.back - self.step,
+        }
+        Some(value)
+    }
+
+    /// Skip `n` values and get the next from the front, in O(1).
+    #[inline]
+    fn nth_front(&mut self, n: usize) -> Option<T> {
+        match T::Unsigned::from_usize(n) {
+            Some(n) if !self.ended && n <= self.steps_left() => {
+                self.front = self.front + n * self.step;
+                self.next_front()
+            }
+            _ => {
+                self.ended = true;
+                None
+            }
+        }
+    }
+
+    /// Skip `n` values and get the next from the back, in O(1).
+    #[inline]
+    fn nth_back_value(&mut self, n: usize) -> Option<T> {
+        match T::Unsigned::from_usize(n) {
+            Some(n) if !self.ended && n <= self.steps_left() => {
+                self.back = self.back - n * self.step;
+                self.next_back_value()
+            }
+            _ => {
+                self.ended = true;
+                None
+            }
+        }
+    }
+}
+
+macro_rules! impl_shard_iterator {
+    ($T:ident) => {
+        impl<P: Permutation<$T>> Iterator for Shard<$T, P> {
+            type Item = $T;
+
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                self.next_front()
+            }
+
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                match self.ended {
+                    false => unsized_hint(to_u128(self.steps_left()), usize::MAX as u128),
+                    true => (0, Some(0)),
+                }
+            }
+
+            #[inline]
+            fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                self.nth_front(n)
+            }
+
+            #[inline]
+            fn last(mut self) -> Option<Self::Item> {
+                self.next_back_value()
+            }
+
+            #[inline]
+            fn count(self) -> usize {
+                usize::try_from(self.remaining()).expect("The remaining length of the shard doesn't fit in a usize.")
+            }
+        }
+
+        impl<P: Permutation<$T>> DoubleEndedIterator for Shard<$T, P> {
+            #[inline]
+            fn next_back(&mut self) -> Option<Self::Item> {
+                self.next_back_value()
+            }
+
+            #[inline]
+            fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+                self.nth_back_value(n)
+            }
+        }
+    };
+    ($T:ident, exact) => {
+        impl_shard_iterator!($T);
+
+        impl<P: Permutation<$T>> ExactSizeIterator for Shard<$T, P> {}
+    };
+}
+
+// A single shard holds the entire sequence, so fits exact size iterators in the same types as a
+// RandomSequence. Unlike a Window, a u64 shard of `n = 1` holds all 2^64 indices, one more than a
+// usize on 64-bit targets, so u64 and i64 shards only report their length by `remaining`.
+impl_shard_iterator!(u8, exact);
+impl_shard_iterator!(u16, exact);
+#[cfg(target_pointer_width = "64")]
+impl_shard_iterator!(u32, exact);
+#[cfg(target_pointer_width = "32")]
+impl_shard_iterator!(u32);
+impl_shard_iterator!(u64);
+impl_shard_iterator!(usize);
+impl_shard_iterator!(u128);
+impl_shard_iterator!(i8, exact);
+impl_shard_iterator!(i16, exact);
+#[cfg(target_pointer_width = "64")]
+impl_shard_iterator!(i32, exact);
+#[cfg(target_pointer_width = "32")]
+impl_shard_iterator!(i32);
+impl_shard_iterator!(i64);
+impl_shard_iterator!(isize);
+impl_shard_iterator!(i128);
+
+impl<T, P> Shards<T, P>
+where
+    T: SequenceInt,
+    P: Permutation<T> + Clone,
+{
+    /// Shard `k` of the `n` shards.
+    #[inline]
+    fn shard(&self, k: usize) -> Shard<T, P> {
+        match self.interleaved {
+            false => Shard::contiguous(self.permutation.clone(), k, self.n),
+            true => Shard::interleaved(self.permutation.clone(), k, self.n),
+        }
+    }
+}
+
+impl<T, P> Iterator for Shards<T, P>
+where
+    T: SequenceInt,
+    P: Permutation<T> + Clone,
+{
+    type Item = Shard<T, P>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.shards.next().map(|k| self.shard(k))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.shards.size_hint()
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.shards.nth(n).map(|k| self.shard(k))
+    }
+}
+
+impl<T, P> DoubleEndedIterator for Shards<T, P>
+where
+    T: SequenceInt,
+    P: Permutation<T> + Clone,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.shards.next_back().map(|k| self.shard(k))
+    }
+}
+
+impl<T, P> ExactSizeIterator for Shards<T, P>
+where
+    T: SequenceInt,
+    P: Permutation<T> + Clone,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::builder::test_configs;
+
+    macro_rules! test_shard {
+        ($name:ident, $type:ident, $unsigned:ident) => {
+            #[test]
+            fn $name() {
+                // the configs small enough to walk every index of
+                let configs = test_configs::<$unsigned>().into_iter().filter(|config| config.max_index() as u128 <= 1000);
+                for config in configs {
+                    let sequence = RandomSequence::<$type>::from(config);
+                    let expected: Vec<$type> = sequence.clone().collect();
+                    for n in [1, 2, 3, 7, 10, 99, 100, 101, 300] {
+                        for interleaved in [false, true] {
+                            let shards = match interleaved {
+                                false => sequence.shards(n),
+                                true => sequence.shards_interleaved(n),
+                            };
+                            assert_eq!(shards.len(), n);
+
+                            // the shards cover every index once, and report their indices
+                            let mut covered = std::vec![false; expected.len()];
+                            let mut lengths = Vec::new();
+                            for (k, shard) in shards.enumerate() {
+                                let single = match interleaved {
+                                    false => sequence.shard(k, n),
+                                    true => sequence.shard_interleaved(k, n),
+                                };
+                                assert!(single.clone().eq(shard.clone()));
+                                let len = shard.remaining() as usize;
+                                assert_eq!(shard.size_hint(), (len, Some(len)));
+                                assert_eq!(shard.clone().count(), len);
+                                lengths.push(len);
+
+                                let mut shard = shard;
+                                let mut previous = None;
+                                while let Some(index) = shard.index() {
+                                    let index = index as $unsigned as usize;
+                                    assert!(!covered[index], "{:?} covers {} twice", config, index);
+                                    covered[index] = true;
+                                    if interleaved {
+                                        assert_eq!(index % n, k);
+                                    } else if let Some(previous) = previous {
+                                        assert_eq!(index, previous + 1);
+                                    }
+                                    previous = Some(index);
+                                    assert_eq!(shard.next(), Some(expected[index]));
+                                }
+                                assert!(shard.exhausted());
+                                assert_eq!((shard.next(), shard.next_back(), shard.remaining()), (None, None, 0));
+                            }
+                            assert!(covered.iter().all(|covered| *covered), "{:?} in {} shards", config, n);
+
+                            // the lengths differ by at most one
+                            let (min, max) = (lengths.iter().min().unwrap(), lengths.iter().max().unwrap());
+                            assert!(max - min <= 1, "{:?} in {} shards: {:?}", config, n, lengths);
+                        }
+                    }
+
+                    // reverse iteration, and nth from both ends consistent with stepping
+                    for k in [0, 1, 2] {
+                        for shard in [sequence.shard(k, 3), sequence.shard_interleaved(k, 3)] {
+                            let forward: Vec<$type> = shard.clone().collect();
+                            assert!(shard.clone().rev().eq(forward.iter().rev().copied()));
+                            assert_eq!(shard.clone().last(), forward.last().copied());
+                            for n in [0, 1, 2, 5, 50, 200] {
+                                // the default nth of an adapter steps one value at a time
+                                let (mut fast, mut walk) = (shard.clone(), shard.clone().inspect(|_| ()));
+                                for _ in 0..3 {
+                                    assert_eq!(fast.nth(n), walk.nth(n));
+                                    assert_eq!(fast.nth_back(n), walk.nth_back(n));
+                                    assert_eq!(fast.remaining(), walk.clone().count() as u128);
+                                }
+                                assert!(shard.clone().step_by(n + 1).eq(forward.iter().copied().step_by(n + 1)));
+                            }
+                        }
+                    }
+                }
+
+                // the shards of a full sequence, without walking them
+                let sequence = RandomSequence::<$type>::new(7, 11);
+                let max_index = $unsigned::MAX;
+                let (first, last) = (sequence.shard(0, 3), sequence.shard(2, 3));
+                assert_eq!((first.remaining(), last.remaining()), ((max_index / 3) as u128 + 1, (max_index / 3) as u128));
+                assert_eq!(last.clone().next_back(), Some(sequence.n(max_index as $type)));
+                assert_eq!(last.index(), Some((max_index - (max_index / 3 - 1)) as $type));
+                let interleaved = sequence.shard_interleaved(2, 3);
+                assert_eq!(interleaved.clone().next_back(), Some(sequence.n((max_index - (max_index - 2) % 3) as $type)));
+                assert_eq!(interleaved.remaining(), (max_index / 3) as u128);
+                assert_eq!(sequence.shard(0, 1).remaining(), sequence.remaining());
+            }
+        };
+    }
+
+    test_shard!(test_u8_shard, u8, u8);
+    test_shard!(test_u16_shard, u16, u16);
+    test_shard!(test_u32_shard, u32, u32);
+    test_shard!(test_u64_shard, u64, u64);
+    test_shard!(test_u128_shard, u128, u128);
+    test_shard!(test_i8_shard, i8, u8);
+    test_shard!(test_i32_shard, i32, u32);
+
+    #[test]
+    fn test_shard_more_than_indices() {
+        let sequence = RandomSequence::<u8>::new(7, 11);
+        assert_eq!(sequence.shard(5, 1000).collect::<Vec<_>>(), [sequence.n(5)]);
+        assert_eq!(sequence.shard_interleaved(255, 1000).collect::<Vec<_>>(), [sequence.n(255)]);
+        assert!(sequence.shard(256, 1000).exhausted());
+        assert_eq!(sequence.shard_interleaved(999, 1000).index(), None);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_shard_hint() {
+        // a single u64 shard holds one more value than a usize, so only remaining is exact
+        let sequence = RandomSequence::<u64>::new(7, 11);
+        assert_eq!(sequence.shard(0, 1).remaining(), 1 << 64);
+        assert_eq!(sequence.shard(0, 1).size_hint(), (usize::MAX, None));
+        assert_eq!(sequence.shard(1, 2).size_hint(), (1 << 63, Some(1 << 63)));
+    }
+
+    #[test]
+    #[should_panic(expected = "below the number of shards")]
+    fn test_shard_out_of_range() {
+        RandomSequence::<u32>::new(7, 11).shard(4, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be positive")]
+    fn test_shards_zero() {
+        RandomSequence::<u32>::new(7, 11).shards(0);
+    }
+}
@@ -0,0 +1,94 @@
+use core::ops::RangeInclusive;
+
+use crate::builder::{QuadraticResidue, RandomSequenceBuilder};
+
+/// An integer type that a [RandomSequence](crate::RandomSequence) can generate.
+///
+/// Each type is backed by the unsigned integer of the same width, which defines the permutation.
+/// Signed integers are converted by reinterpreting their two's complement bit pattern, so wrapping
+/// arithmetic on the unsigned values is equivalent to wrapping arithmetic on the signed values.
+pub trait SequenceInt: Copy {
+    /// The unsigned integer type of the same width.
+    type Unsigned: QuadraticResidue;
+
+    /// Reinterpret this value as its unsigned counterpart.
+    fn to_unsigned(self) -> Self::Unsigned;
+
+    /// Reinterpret an unsigned value as this type.
+    fn from_unsigned(value: Self::Unsigned) -> Self;
+}
+
+macro_rules! impl_unsigned_int {
+    ($type:ident) => {
+        impl SequenceInt for $type {
+            type Unsigned = $type;
+
+            #[inline(always)]
+            fn to_unsigned(self) -> Self::Unsigned {
+                self
+            }
+
+            #[inline(always)]
+            fn from_unsigned(value: Self::Unsigned) -> Self {
+                value
+            }
+        }
+    };
+}
+
+macro_rules! impl_signed_int {
+    ($type:ident, $unsigned:ident) => {
+        impl SequenceInt for $type {
+            type Unsigned = $unsigned;
+
+            #[inline(always)]
+            fn to_unsigned(self) -> Self::Unsigned {
+                self as $unsigned
+            }
+
+            #[inline(always)]
+            fn from_unsigned(value: Self::Unsigned) -> Self {
+                value as $type
+            }
+        }
+
+        impl RandomSequenceBuilder<$unsigned> {
+            /// Map the sequence onto the signed values in `range`, such as `-1000..=1000`, for use
+            /// with a signed [RandomSequence](crate::RandomSequence).
+            ///
+            /// Equivalent to [RandomSequenceBuilder::with_signed_range_step] with a step of 1.
+            ///
+            /// # Panics
+            ///
+            /// If `range` is empty.
+            pub fn with_signed_range(self, range: RangeInclusive<$type>) -> Self {
+                self.with_signed_range_step(range, 1)
+            }
+
+            /// Map the sequence onto every `step`-th signed value in `range`, starting from
+            /// `range.start()`, for use with a signed [RandomSequence](crate::RandomSequence).
+            ///
+            /// # Panics
+            ///
+            /// If `range` is empty, or `step` is zero.
+            pub fn with_signed_range_step(self, range: RangeInclusive<$type>, step: $unsigned) -> Self {
+                let (start, end) = range.into_inner();
+                assert!(start <= end, "The range must contain at least one value.");
+                self.with_range_span(start as $unsigned, end.wrapping_sub(start) as $unsigned, step)
+            }
+        }
+    };
+}
+
+impl_unsigned_int!(u8);
+impl_unsigned_int!(u16);
+impl_unsigned_int!(u32);
+impl_unsigned_int!(u64);
+impl_unsigned_int!(u128);
+impl_unsigned_int!(usize);
+impl_signed_int!(i8, u8);
+impl_signed_int!(i16, u16);
+impl_signed_int!(i32, u32);
+impl_signed_int!(i64, u64);
+impl_signed_int!(i128, u128);
+impl_signed_int!(isize, usize);
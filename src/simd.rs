@@ -0,0 +1,606 @@
+use num_traits::{FromPrimitive, ToPrimitive};
+
+use crate::builder::{type_bits, Backend, Keys, RandomSequenceBuilder};
+use crate::permutation::BuilderPermutation;
+use crate::sequence::BATCH;
+use crate::signed::SequenceInt;
+use crate::QuadraticResidue;
+
+/// `2^52` as an `f64`, whose mantissa holds any integer below `2^52` exactly in its low bits.
+const MAGIC: f64 = 4503599627370496.0;
+
+/// The bits of [MAGIC].
+const MAGIC_BITS: u64 = 0x4330_0000_0000_0000;
+
+/// The largest `c` of a prime `2^k - c` of the wide kernel, see [Fold].
+const FOLD_LIMIT: u64 = 1 << 12;
+
+/// The [Backend::QuadraticResidue] permutation of a [RandomSequenceBuilder] of up to 64 bits,
+/// evaluated over the 64-bit lanes of SSE2 or AVX2 vectors.
+///
+/// Permutations of up to 32 bits hold a value below `2^32` in each lane, so `x * x` fits in the
+/// lane, and `x * x mod prime` is reduced with a quotient estimated in `f64` rather than a
+/// division. The estimate is never below the true quotient and at most one above it, so a single
+/// correction gives the exact remainder.
+///
+/// Wider permutations square each lane from its 32-bit halves into 128 bits, and fold the square
+/// modulo a prime `2^k - c` as the scalar `mul_mod` does, see [Fold]. Either way the values are
+/// bit-identical to the scalar permutation.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Lanes {
+    mask: u64,
+    prime: Residue,
+    tail_window: Option<(u64, Residue)>,
+    intermediate_offset: u64,
+    intermediate_xor: u64,
+    rounds: u8,
+
+    /// Whether the CPU supports AVX2, detected once with the lanes.
+    avx2: bool,
+}
+
+/// A prime of the lanes, with the constants of its residue.
+#[derive(Debug, Clone, Copy)]
+struct Residue {
+    prime: u64,
+
+    /// `prime / 2 + 1`, the smallest value mapped to `prime - x^2 mod prime`.
+    half: u64,
+
+    /// `1 / prime`, to estimate the quotient of `x^2 / prime`.
+    inverse: f64,
+
+    /// The folding constants of a prime above `2^32`, whose squares don't fit in a lane.
+    fold: Option<Fold>,
+}
+
+impl Residue {
+    /// The residue of a prime, or `None` if the prime is above `2^32` and too far below a power of
+    /// two to fold.
+    fn new(prime: u64) -> Option<Self> {
+        let fold = match prime > u32::MAX as u64 {
+            true => Some(Fold::new(prime)?),
+            false => None,
+        };
+        Some(Self {
+            prime,
+            half: prime / 2 + 1,
+            inverse: 1.0 / prime as f64,
+            fold,
+        })
+    }
+}
+
+/// The `(k, 2^k - 1, c)` parameters to fold a square modulo a prime `2^k - c` above `2^32`, where
+/// `2^k` is the smallest power of two above the prime, so `2^k = c mod prime`.
+///
+/// The square of a lane below the prime is `q * 2^k + r`, for `q, r < 2^k`, which folds down to
+/// `q * c + r`, below `2^(k + 12)` with `c < 2^12`. A second fold leaves less than `2^k + 2^25`,
+/// which a single subtraction of the prime reduces.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(any(feature = "std", target_feature = "avx2")), allow(dead_code))]
+struct Fold {
+    bits: u32,
+    mask: u64,
+    c: u64,
+}
+
+impl Fold {
+    fn new(prime: u64) -> Option<Self> {
+        let bits = 64 - prime.leading_zeros();
+        let mask = u64::MAX >> (64 - bits);
+        let c = mask - prime + 1;
+        (c < FOLD_LIMIT).then_some(Self { bits, mask, c })
+    }
+}
+
+impl Lanes {
+    /// The lanes of a permutation, or `None` if the permutation can't be vectorised, as it's wider
+    /// than 64 bits, uses another backend, or a custom prime that can't be folded.
+    pub(crate) fn new<U: QuadraticResidue>(config: &RandomSequenceBuilder<U>, keys: &Keys<U>) -> Option<Self> {
+        if type_bits::<U>() > 64 || !matches!(config.backend, Backend::QuadraticResidue) {
+            return None;
+        }
+
+        let widen = |value: U| value.to_u64().expect("Values up to 64 bits fit in a u64.");
+        let (mask, prime) = (widen(config.mask()), Residue::new(widen(config.prime))?);
+        // the kernels of lanes below 2^32 compare only the low halves, and the tail window's
+        // residue is always one of them
+        let tail_window = match keys.tail_window.map(|(start, prime)| (widen(start), widen(prime))) {
+            Some((_, tail_prime)) if tail_prime > u32::MAX as u64 => return None,
+            Some((start, tail_prime)) => Some((start, Residue::new(tail_prime)?)),
+            None => None,
+        };
+        let avx2 = avx2();
+        if mask > u32::MAX as u64 && (prime.fold.is_none() || !avx2) {
+            return None;
+        }
+
+        Some(Self {
+            mask,
+            prime,
+            tail_window,
+            intermediate_offset: widen(keys.intermediate_offset),
+            intermediate_xor: widen(keys.intermediate_xor),
+            rounds: config.rounds,
+            avx2,
+        })
+    }
+
+    /// Write the value of each index of the permutation to `values`, the same values as
+    /// [Permutation::apply](crate::Permutation::apply) for each index.
+    pub(crate) fn apply<T: SequenceInt>(
+        &self,
+        permutation: &BuilderPermutation<T::Unsigned>,
+        indices: &[T::Unsigned],
+        values: &mut [T],
+    ) {
+        let (config, keys) = (&permutation.config, &permutation.keys);
+        let mut lanes = [0u64; BATCH];
+        for (indices, values) in indices.chunks(BATCH).zip(values.chunks_mut(BATCH)) {
+            for (lane, index) in lanes.iter_mut().zip(indices) {
+                *lane = config.add_index(keys.start_index, *index).to_u64().expect("Values up to 64 bits fit in a u64.");
+            }
+
+            // the unused lanes of the last batch are permuted too, but never read
+            let width = indices.len().next_multiple_of(16);
+            self.permute(&mut lanes[..width]);
+
+            for (lane, value) in lanes.iter().zip(values) {
+                let mut permuted = T::Unsigned::from_u64(*lane).expect("Lanes hold values of the type.");
+                if !config.in_domain(permuted) {
+                    // continue cycle-walking the rare values outside the domain
+                    permuted = config.permute(permuted, keys);
+                }
+                *value = T::from_unsigned(config.map_range(permuted));
+            }
+        }
+    }
+
+    /// Apply the residue passes and rounds to each lane in place, using AVX2 if the CPU supports
+    /// it, and otherwise SSE2. The length of `lanes` must be a multiple of 16.
+    fn permute(&self, lanes: &mut [u64]) {
+        match (self.avx2, self.prime.fold) {
+            // SAFETY: the CPU supports AVX2
+            #[cfg(any(feature = "std", target_feature = "avx2"))]
+            (true, Some(fold)) => unsafe { avx2::permute_wide(self, fold, lanes) },
+            // SAFETY: the CPU supports AVX2
+            #[cfg(any(feature = "std", target_feature = "avx2"))]
+            (true, None) => unsafe { avx2::permute(self, lanes) },
+            // SAFETY: SSE2 is part of the x86_64 baseline, and lanes above 2^32 require AVX2
+            _ => unsafe { sse2::permute(self, lanes) },
+        }
+    }
+}
+
+/// Whether the CPU supports AVX2. Without the `std` feature, AVX2 is only used when the target
+/// enables it at compile time.
+fn avx2() -> bool {
+    #[cfg(feature = "std")]
+    let avx2 = std::is_x86_feature_detected!("avx2");
+    #[cfg(not(feature = "std"))]
+    let avx2 = cfg!(target_feature = "avx2");
+    avx2
+}
+
+/// Apply a function to each of the 4 independent vectors of its arguments, interleaving their
+/// instructions so the latency of one vector's residue overlaps with the others.
+macro_rules! each {
+    ($f:ident($($arg:ident),+)) => {
+        [$f($($arg[0]),+), $f($($arg[1]),+), $f($($arg[2]),+), $f($($arg[3]),+)]
+    };
+}
+
+// each batch fills whole chunks of 4 vectors of the widest 4 lanes
+const _: () = assert!(BATCH % 16 == 0);
+
+/// Apply the residue passes and rounds to each chunk of 4 vectors of `values` in place, where
+/// `|x| pass` is the residue pass of 4 vectors `x`.
+macro_rules! rounds {
+    (
+        $lanes:ident, $values:ident, |$x:ident| $pass:expr,
+        $vector:ident, $width:literal, $load:ident, $store:ident, $add:ident, $and:ident, $xor:ident
+    ) => {
+        let mask = splat($lanes.mask);
+        for chunk in $values.chunks_exact_mut(4 * $width) {
+            let vectors = chunk.as_mut_ptr() as *mut $vector;
+            let $x: Vectors = [0, 1, 2, 3].map(|i| $load(vectors.add(i)));
+            let mut value = $pass;
+            let (mut offset, mut xor) = ($lanes.intermediate_offset, $lanes.intermediate_xor);
+            for _ in 1..$lanes.rounds {
+                // the offset only wraps in the type, but only its masked bits are used
+                let (offset_lanes, xor_lanes) = (splat(offset), splat(xor & $lanes.mask));
+                let x = each!($add(value, offset_lanes));
+                let x = each!($and(x, mask));
+                let $x = each!($xor(x, xor_lanes));
+                value = $pass;
+                (offset, xor) = Const::<u64>::next_round_key(offset, xor, $lanes.intermediate_offset);
+            }
+            for (i, value) in value.into_iter().enumerate() {
+                $store(vectors.add(i), value);
+            }
+        }
+    };
+}
+
+/// Generate the kernel over the 64-bit lanes of one vector width, from its intrinsics, and the
+/// kernel of lanes above `2^32` from the `wide` intrinsics of 64-bit comparisons and shifts.
+macro_rules! impl_lanes {
+    (
+        $module:ident, $feature:literal, $width:literal, $vector:ident, $double:ident,
+        $set1:ident, $set1_pd:ident, $load:ident, $store:ident,
+        $add:ident, $sub:ident, $mul:ident, $and:ident, $andnot:ident, $or:ident, $xor:ident,
+        $cmpeq:ident, $srli:ident,
+        $cast_pd:ident, $cast_si:ident, $add_pd:ident, $sub_pd:ident, $mul_pd:ident
+        $(, wide: $cmpeq_wide:ident, $cmpgt_wide:ident, $slli:ident, $sll:ident, $srl:ident)?
+    ) => {
+        mod $module {
+            use core::arch::x86_64::*;
+
+            use super::{Lanes, Residue, MAGIC, MAGIC_BITS};
+            use crate::constant::Const;
+
+            /// 4 independent vectors, permuted together.
+            type Vectors = [$vector; 4];
+
+            /// Permute each lane below `2^32`, see [Lanes::permute]. The length of `values` must
+            /// be a multiple of 4 vectors.
+            #[target_feature(enable = $feature)]
+            pub(super) unsafe fn permute(lanes: &Lanes, values: &mut [u64]) {
+                rounds!(lanes, values, |x| pass(lanes, x), $vector, $width, $load, $store, $add, $and, $xor);
+            }
+
+            /// A single residue pass, see the `permute_pass` of the builder.
+            #[inline(always)]
+            unsafe fn pass(lanes: &Lanes, x: Vectors) -> Vectors {
+                let y = qpr(x, lanes.prime);
+                let Some((start, tail)) = lanes.tail_window else {
+                    return y;
+                };
+
+                // the residue over the tail window, rotated down by one
+                let (below, one, last) = (splat(start - 1), splat(1), splat(tail.prime - 1));
+                let start = splat(start);
+                let at_start = each!(eq(y, start));
+                let local = each!($sub(y, start));
+                let local = each!($sub(local, one));
+                let local = qpr(each!(select(at_start, last, local)), tail);
+                let at_last = each!(eq(local, last));
+                let folded = each!($add(local, start));
+                let folded = each!($add(folded, one));
+                let folded = each!(select(at_last, start, folded));
+                let in_tail = each!(lt(below, y));
+                each!(select(in_tail, folded, y))
+            }
+
+            /// The quadratic prime residue of each lane below `2^32`.
+            #[inline(always)]
+            unsafe fn qpr(x: Vectors, residue: Residue) -> Vectors {
+                let (prime, half) = (splat(residue.prime), splat(residue.half));
+                let square = mul_mod(x, prime, residue.inverse);
+                let negated = each!($sub(prime, square));
+                let low = each!(lt(x, half));
+                let y = each!(select(low, square, negated));
+                let permuted = each!(lt(x, prime));
+                each!(select(permuted, y, x))
+            }
+
+            /// `x * x mod prime` for each lane below `prime`.
+            #[inline(always)]
+            unsafe fn mul_mod(x: Vectors, prime: Vectors, inverse: f64) -> Vectors {
+                let inverse = [$set1_pd(inverse); 4];
+                let square = each!($mul(x, x));
+                let float = each!(to_f64(x));
+                let quotient = each!($mul_pd(float, float));
+                let quotient = each!($mul_pd(quotient, inverse));
+                let quotient = each!(to_u64(quotient));
+                let product = each!($mul(quotient, prime));
+                let remainder = each!($sub(square, product));
+                let negative = each!(negative(remainder));
+                let correction = each!($and(prime, negative));
+                each!($add(remainder, correction))
+            }
+
+            /// Convert each lane below `2^52` to an `f64`.
+            #[inline(always)]
+            unsafe fn to_f64(x: $vector) -> $double {
+                $sub_pd($cast_pd($or(x, $set1(MAGIC_BITS as i64))), $set1_pd(MAGIC))
+            }
+
+            /// Round each non-negative `f64` lane below `2^52` to an integer.
+            #[inline(always)]
+            unsafe fn to_u64(x: $double) -> $vector {
+                $sub($cast_si($add_pd(x, $set1_pd(MAGIC))), $set1(MAGIC_BITS as i64))
+            }
+
+            /// A mask of the lanes where `a < b`, for lanes below `2^32`.
+            #[inline(always)]
+            unsafe fn lt(a: $vector, b: $vector) -> $vector {
+                negative($sub(a, b))
+            }
+
+            /// A mask of the negative lanes above `-2^32`, the ones of their high half shifted down.
+            #[inline(always)]
+            unsafe fn negative(x: $vector) -> $vector {
+                $srli::<32>(x)
+            }
+
+            /// A mask of the lanes where `a == b`, for lanes below `2^32`.
+            #[inline(always)]
+            unsafe fn eq(a: $vector, b: $vector) -> $vector {
+                $cmpeq(a, b)
+            }
+
+            /// The lanes of `a` where `mask` is set, and of `b` elsewhere.
+            ///
+            /// The masks of [lt] only cover the low half of each lane, as broadcasting a 64-bit
+            /// comparison would take a shuffle, so the high half is taken from `b`. The high halves
+            /// are zero in every lane in use, and only differ in lanes which are discarded.
+            #[inline(always)]
+            unsafe fn select(mask: $vector, a: $vector, b: $vector) -> $vector {
+                $or($and(mask, a), $andnot(mask, b))
+            }
+
+            /// The same value in every lane of each vector.
+            #[inline(always)]
+            unsafe fn splat(value: u64) -> Vectors {
+                [$set1(value as i64); 4]
+            }
+
+            $(
+            use super::Fold;
+
+            /// Permute each lane up to 64 bits, with a prime above `2^32` folded by `fold`, see
+            /// [Lanes::permute]. The length of `values` must be a multiple of 4 vectors.
+            #[target_feature(enable = $feature)]
+            pub(super) unsafe fn permute_wide(lanes: &Lanes, fold: Fold, values: &mut [u64]) {
+                rounds!(lanes, values, |x| pass_wide(lanes, fold, x), $vector, $width, $load, $store, $add, $and, $xor);
+            }
+
+            /// [pass] of lanes up to 64 bits, with a prime above `2^32` and a tail window prime
+            /// below it.
+            #[inline(always)]
+            unsafe fn pass_wide(lanes: &Lanes, fold: Fold, x: Vectors) -> Vectors {
+                let y = qpr_wide(x, lanes.prime, fold);
+                let Some((start, tail)) = lanes.tail_window else {
+                    return y;
+                };
+
+                // the window ends at a multiple of 2^32 and holds less than 2^32 values, so the
+                // offsets within it, and the high halves of the values in it, fit [pass]
+                let (one, last) = (splat(1), splat(tail.prime - 1));
+                let start = splat(start);
+                let at_start = each!($cmpeq_wide(y, start));
+                let local = each!($sub(y, start));
+                let local = each!($sub(local, one));
+                let local = qpr(each!(select(at_start, last, local)), tail);
+                let at_last = each!(eq(local, last));
+                let folded = each!($add(local, start));
+                let folded = each!($add(folded, one));
+                let folded = each!(select(at_last, start, folded));
+                let below = each!(lt_wide(y, start));
+                each!(select(below, y, folded))
+            }
+
+            /// The quadratic prime residue of each lane up to 64 bits, with a prime above `2^32`.
+            #[inline(always)]
+            unsafe fn qpr_wide(x: Vectors, residue: Residue, fold: Fold) -> Vectors {
+                let (prime, half) = (splat(residue.prime), splat(residue.half));
+                let square = mul_mod_wide(x, prime, fold);
+                let negated = each!($sub(prime, square));
+                let low = each!(lt_wide(x, half));
+                let y = each!(select(low, square, negated));
+                let permuted = each!(lt_wide(x, prime));
+                each!(select(permuted, y, x))
+            }
+
+            /// `x * x mod prime` for each lane below a prime above `2^32`, see [Fold].
+            #[inline(always)]
+            unsafe fn mul_mod_wide(x: Vectors, prime: Vectors, fold: Fold) -> Vectors {
+                let (mask, c) = (splat(fold.mask), splat(fold.c));
+                let down = [_mm_cvtsi64_si128(fold.bits as i64); 4];
+                let up = [_mm_cvtsi64_si128(64 - fold.bits as i64); 4];
+
+                // the 128-bit square from the products of the 32-bit halves, carrying between the
+                // 32-bit limbs so no sum overflows a lane
+                let x_high = each!(high(x));
+                let low_low = each!($mul(x, x));
+                let low_high = each!($mul(x, x_high));
+                let high_high = each!($mul(x_high, x_high));
+                let (cross_low, cross_high) = (each!(low(low_high)), each!(high(low_high)));
+                let limb = each!(high(low_low));
+                let limb = each!($add(limb, cross_low));
+                let limb = each!($add(limb, cross_low));
+                let carry = each!(high(limb));
+                let square_high = each!($add(high_high, cross_high));
+                let square_high = each!($add(square_high, cross_high));
+                let square_high = each!($add(square_high, carry));
+                let limb = each!(shift_up(limb));
+                let square_low = each!(low(low_low));
+                let square_low = each!($or(limb, square_low));
+
+                // q * 2^k + r folds to q * c + r, summed in 32-bit limbs as q and r span 64 bits
+                let (q, r) = split(square_high, square_low, down, up, mask);
+                let q_high = each!(high(q));
+                let low_c = each!($mul(q, c));
+                let high_c = each!($mul(q_high, c));
+                let (low_c_low, r_low) = (each!(low(low_c)), each!(low(r)));
+                let limb_low = each!($add(low_c_low, r_low));
+                let (low_c_high, r_high, high_c_low) = (each!(high(low_c)), each!(high(r)), each!(low(high_c)));
+                let limb = each!(high(limb_low));
+                let limb = each!($add(limb, low_c_high));
+                let limb = each!($add(limb, r_high));
+                let limb = each!($add(limb, high_c_low));
+                let (carry, high_c_high) = (each!(high(limb)), each!(high(high_c)));
+                let folded_high = each!($add(high_c_high, carry));
+                let limb = each!(shift_up(limb));
+                let limb_low = each!(low(limb_low));
+                let folded_low = each!($or(limb, limb_low));
+
+                // q < 2^13 after the second fold, and a lane only overflows with k = 64, leaving a
+                // small value that the carried out 2^64 = c mod prime is added back to
+                let (q, r) = split(folded_high, folded_low, down, up, mask);
+                let q_c = each!($mul(q, c));
+                let y = each!($add(q_c, r));
+                let overflow = each!(lt_wide(y, r));
+                let carried = each!($and(overflow, c));
+                let y = each!($add(y, carried));
+                let reduced = each!(lt_wide(y, prime));
+                let correction = each!($andnot(reduced, prime));
+                each!($sub(y, correction))
+            }
+
+            /// Split each 128-bit value of the `high` and `low` lanes into `(x >> k, x & mask)`,
+            /// where `x < 2^(2k)` and `down` and `up` shift by `k` and `64 - k`.
+            #[inline(always)]
+            unsafe fn split(high: Vectors, low: Vectors, down: [__m128i; 4], up: [__m128i; 4], mask: Vectors) -> (Vectors, Vectors) {
+                // with k = 64 the low lanes shift out entirely
+                let high_bits = each!($sll(high, up));
+                let low_bits = each!($srl(low, down));
+                (each!($or(high_bits, low_bits)), each!($and(low, mask)))
+            }
+
+            /// A mask of the lanes where `a < b`, comparing the whole lanes.
+            #[inline(always)]
+            unsafe fn lt_wide(a: $vector, b: $vector) -> $vector {
+                let sign = $set1(i64::MIN);
+                $cmpgt_wide($xor(b, sign), $xor(a, sign))
+            }
+
+            /// The high 32 bits of each lane.
+            #[inline(always)]
+            unsafe fn high(x: $vector) -> $vector {
+                $srli::<32>(x)
+            }
+
+            /// The low 32 bits of each lane.
+            #[inline(always)]
+            unsafe fn low(x: $vector) -> $vector {
+                $and(x, $set1(u32::MAX as i64))
+            }
+
+            /// The low 32 bits of each lane, shifted to its high half.
+            #[inline(always)]
+            unsafe fn shift_up(x: $vector) -> $vector {
+                $slli::<32>(x)
+            }
+            )?
+        }
+    };
+}
+
+impl_lanes!(
+    sse2, "sse2", 2, __m128i, __m128d,
+    _mm_set1_epi64x, _mm_set1_pd, _mm_loadu_si128, _mm_storeu_si128,
+    _mm_add_epi64, _mm_sub_epi64, _mm_mul_epu32, _mm_and_si128, _mm_andnot_si128, _mm_or_si128, _mm_xor_si128,
+    _mm_cmpeq_epi32, _mm_srli_epi64,
+    _mm_castsi128_pd, _mm_castpd_si128, _mm_add_pd, _mm_sub_pd, _mm_mul_pd
+);
+
+// SSE2 has no 64-bit comparisons, and folding its 2 lanes is slower than the scalar permutation
+#[cfg(any(feature = "std", target_feature = "avx2"))]
+impl_lanes!(
+    avx2, "avx2", 4, __m256i, __m256d,
+    _mm256_set1_epi64x, _mm256_set1_pd, _mm256_loadu_si256, _mm256_storeu_si256,
+    _mm256_add_epi64, _mm256_sub_epi64, _mm256_mul_epu32, _mm256_and_si256, _mm256_andnot_si256, _mm256_or_si256,
+    _mm256_xor_si256, _mm256_cmpeq_epi32, _mm256_srli_epi64,
+    _mm256_castsi256_pd, _mm256_castpd_si256, _mm256_add_pd, _mm256_sub_pd, _mm256_mul_pd,
+    wide: _mm256_cmpeq_epi64, _mm256_cmpgt_epi64, _mm256_slli_epi64, _mm256_sll_epi64, _mm256_srl_epi64
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::KeySchedule;
+
+    /// The scalar residue passes and rounds of a lane, for comparison with the kernels.
+    fn permute_scalar<U: QuadraticResidue>(config: &RandomSequenceBuilder<U>, keys: &Keys<U>, lanes: &mut [u64]) {
+        let mask = config.mask();
+        for lane in lanes {
+            let mut value = config.permute_pass(U::from_u64(*lane).unwrap(), keys.tail_window);
+            let (mut offset, mut xor) = (keys.intermediate_offset, keys.intermediate_xor);
+            for _ in 1..config.rounds {
+                value = config.permute_pass((value.wrapping_add(&offset) & mask) ^ (xor & mask), keys.tail_window);
+                offset = offset.wrapping_add(&keys.intermediate_offset);
+                xor = xor ^ offset;
+            }
+            *lane = value.to_u64().unwrap();
+        }
+    }
+
+    /// Check the kernels against the scalar permutation of each config.
+    fn check_lanes<U: QuadraticResidue + core::fmt::Debug>(configs: &[RandomSequenceBuilder<U>]) {
+        for config in configs {
+            let keys = config.keys();
+            let mask = widen(config.mask());
+            let Some(lanes) = Lanes::new(config, &keys) else {
+                // lanes above 2^32 are only vectorised with AVX2
+                assert!(mask > u32::MAX as u64 && !avx2(), "{:?}", config);
+                continue;
+            };
+
+            // the extremes of the reduction around zero, the prime, half the prime, the tail window
+            // and the mask, and a spread of values between them
+            let (prime, half) = (widen(config.prime), widen(config.prime) / 2);
+            let start = keys.tail_window.map_or(mask, |(start, _)| widen(start));
+            let mut input = std::vec::Vec::new();
+            for edge in [0, 4, prime, half, start, mask] {
+                input.extend((0..8).map(|i| edge.wrapping_add(i).wrapping_sub(4) & mask));
+            }
+            input.extend((0..4096 - input.len() as u64).map(|i| i.wrapping_mul(0x9e3779b97f4a7c15) & mask));
+
+            let mut expected = input.clone();
+            permute_scalar(config, &keys, &mut expected);
+            // lanes above 2^32 are only folded by the AVX2 kernel
+            if lanes.prime.fold.is_none() {
+                let mut sse2 = input.clone();
+                unsafe { sse2::permute(&lanes, &mut sse2) };
+                assert_eq!(sse2, expected, "{:?}", config);
+            }
+            let mut dispatched = input.clone();
+            lanes.permute(&mut dispatched);
+            assert_eq!(dispatched, expected, "{:?}", config);
+        }
+    }
+
+    fn widen<U: QuadraticResidue>(value: U) -> u64 {
+        value.to_u64().unwrap()
+    }
+
+    #[test]
+    fn test_lanes() {
+        let config = RandomSequenceBuilder::<u32>::new(7, 11).with_key_schedule(KeySchedule::Seeded).with_fold_tail(true);
+        check_lanes(&[
+            config,
+            config.with_rounds(1),
+            config.with_rounds(5),
+            config.with_domain(1 << 16),
+            config.with_domain(1000),
+            config.with_domain(3),
+            RandomSequenceBuilder::<u32>::new(7, 11),
+        ]);
+
+        // u64 permutations fold their squares above 32 bits, and use the kernel of u32 below
+        let config = RandomSequenceBuilder::<u64>::new(7, 11).with_key_schedule(KeySchedule::Seeded).with_fold_tail(true);
+        check_lanes(&[
+            config,
+            config.with_rounds(1),
+            config.with_rounds(5),
+            config.with_bits(33),
+            config.with_domain(1 << 40),
+            config.with_domain(1_000_000_000_000),
+            config.with_bits(47),
+            config.with_bits(63),
+            config.with_domain(1000),
+            RandomSequenceBuilder::<u64>::new(7, 11),
+        ]);
+
+        // wider types, other backends, and primes too far below a power of two aren't vectorised
+        let config = RandomSequenceBuilder::<u128>::new(7, 11);
+        assert!(Lanes::new(&config, &config.keys()).is_none());
+        let config = RandomSequenceBuilder::<u32>::new(7, 11).with_backend(Backend::Feistel { rounds: 4 });
+        assert!(Lanes::new(&config, &config.keys()).is_none());
+        let config = RandomSequenceBuilder::<u64>::try_from_spec(7, 11, 3, 5, 9223370937343148051, 9).unwrap();
+        assert!(Lanes::new(&config, &config.keys()).is_none());
+    }
+}
@@ -0,0 +1,211 @@
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::vec;
+
+use crate::permutation::Permutation;
+use crate::sequence::RandomSequence;
+
+/// A [Permutation] backed by lookup tables of a materialized `u8` or `u16` sequence, so each value
+/// and index costs a single table read instead of recomputing the permutation.
+///
+/// The forward table holds the value of each index, and the inverse table holds the index of each
+/// value. Built by `RandomSequence::materialize`, with `Box<[T]>` tables sized to the sequence
+/// when the `alloc` feature is enabled, or by `RandomSequence::materialize_into`, borrowing
+/// `[T; 256]` or `[T; 65536]` arrays for no_std targets without an allocator.
+///
+/// ```rust
+/// use rand_unique::RandomSequence;
+///
+/// let sequence = RandomSequence::<u8>::new(10, 2);
+/// let (mut forward, mut inverse) = ([0; 256], [0; 256]);
+/// let table = sequence.materialize_into(&mut forward, &mut inverse);
+/// assert_eq!(table.n(100), sequence.n(100));
+/// assert_eq!(table.index_of(sequence.n(100)), 100);
+/// assert!(table.clone().eq(sequence));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TablePermutation<T, Table> {
+    max_index: T,
+    forward: Table,
+    inverse: Table,
+}
+
+macro_rules! impl_table {
+    ($type:ident, $len:literal) => {
+        impl<Table> TablePermutation<$type, Table>
+        where
+            Table: AsRef<[$type]>,
+        {
+            /// The value of each index in `0..=max_index`.
+            #[inline]
+            pub fn forward(&self) -> &[$type] {
+                &self.forward.as_ref()[..=self.max_index as usize]
+            }
+
+            /// The index of each value, indexed by the value. Only the entries of values in the
+            /// sequence are meaningful, see [Permutation::invert].
+            #[inline]
+            pub fn inverse(&self) -> &[$type] {
+                self.inverse.as_ref()
+            }
+        }
+
+        impl<Table> Permutation<$type> for TablePermutation<$type, Table>
+        where
+            Table: AsRef<[$type]>,
+        {
+            #[inline]
+            fn max_index(&self) -> $type {
+                self.max_index
+            }
+
+            #[inline(always)]
+            fn apply(&self, index: $type) -> $type {
+                self.forward.as_ref()[index as usize]
+            }
+
+            /// The inverse table holds a stale entry for values outside the sequence, so the index
+            /// is checked against the forward table.
+            #[inline]
+            fn invert(&self, value: $type) -> Option<$type> {
+                let index = self.inverse.as_ref()[value as usize];
+                (index <= self.max_index && self.forward.as_ref()[index as usize] == value).then_some(index)
+            }
+        }
+
+        impl<P> RandomSequence<$type, P>
+        where
+            P: Permutation<$type>,
+        {
+            /// Materialize the sequence into a forward and an inverse lookup table, each sized to
+            /// the sequence, with the same `n`, `index_of` and iterator API. Requires the `alloc`
+            /// feature.
+            ///
+            /// The materialized sequence continues from the current position of this sequence.
+            #[cfg(feature = "alloc")]
+            pub fn materialize(&self) -> RandomSequence<$type, TablePermutation<$type, Box<[$type]>>> {
+                let max_index = self.permutation.max_index();
+                let mut forward = vec![0; max_index as usize + 1].into_boxed_slice();
+                let mut inverse = vec![0; $len].into_boxed_slice();
+                self.fill_tables(&mut forward, &mut inverse);
+                self.with_table(TablePermutation { max_index, forward, inverse })
+            }
+
+            /// Materialize the sequence into borrowed fixed-size arrays, for targets without an
+            /// allocator, see `RandomSequence::materialize`.
+            ///
+            #[doc = concat!("Both tables hold ", stringify!($len), " entries regardless of the domain of the sequence, and can be")]
+            /// placed in a `static` rather than on the stack.
+            pub fn materialize_into<'a>(
+                &self,
+                forward: &'a mut [$type; $len],
+                inverse: &'a mut [$type; $len],
+            ) -> RandomSequence<$type, TablePermutation<$type, &'a [$type]>> {
+                self.fill_tables(forward, inverse);
+                self.with_table(TablePermutation {
+                    max_index: self.permutation.max_index(),
+                    forward: &forward[..],
+                    inverse: &inverse[..],
+                })
+            }
+
+            /// Write the value of each index to `forward`, and the index of each value to `inverse`.
+            fn fill_tables(&self, forward: &mut [$type], inverse: &mut [$type]) {
+                for index in 0..=self.permutation.max_index() {
+                    let value = self.permutation.apply(index);
+                    forward[index as usize] = value;
+                    inverse[value as usize] = index;
+                }
+            }
+
+            /// Wrap a table in a sequence at the same position as this one.
+            #[inline]
+            fn with_table<Table>(&self, permutation: TablePermutation<$type, Table>) -> RandomSequence<$type, TablePermutation<$type, Table>>
+            where
+                Table: AsRef<[$type]>,
+            {
+                RandomSequence {
+                    permutation,
+                    current_index: self.current_index,
+                    ended: self.ended,
+                }
+            }
+        }
+    };
+}
+
+impl_table!(u8, 256);
+impl_table!(u16, 65536);
+
+#[cfg(test)]
+mod tests {
+    use std::boxed::Box;
+    use std::vec;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::builder::{test_configs, RandomSequenceBuilder};
+
+    /// A zeroed array on the heap, as the `u16` tables don't fit on the stack of a test thread.
+    fn array<T: Copy + Default + core::fmt::Debug, const N: usize>() -> Box<[T; N]> {
+        vec![T::default(); N].into_boxed_slice().try_into().unwrap()
+    }
+
+    macro_rules! test_table {
+        ($name:ident, $type:ident, $len:literal) => {
+            #[test]
+            fn $name() {
+                let (mut forward, mut inverse) = (array::<$type, $len>(), array::<$type, $len>());
+                for config in test_configs::<$type>() {
+                    let sequence = config.into_iter();
+                    let table = sequence.materialize_into(&mut forward, &mut inverse);
+                    assert_eq!(table.permutation.max_index(), Permutation::<$type>::max_index(&sequence.permutation));
+                    assert_eq!(table.permutation.forward().len(), config.max_index() as usize + 1);
+
+                    // the same values, indices and membership as the computed sequence
+                    for value in 0..=$type::MAX {
+                        assert_eq!(table.n(value), sequence.n(value));
+                        assert_eq!(table.contains(value), sequence.contains(value));
+                        if sequence.contains(value) {
+                            assert_eq!(table.index_of(value), sequence.index_of(value));
+                        }
+                    }
+
+                    // the same iterators
+                    assert_eq!(table.size_hint(), sequence.size_hint());
+                    assert!(table.clone().eq(sequence.clone()));
+
+                    #[cfg(feature = "alloc")]
+                    {
+                        let boxed = sequence.materialize();
+                        assert_eq!(boxed.permutation.forward(), table.permutation.forward());
+                        // the borrowed tables hold stale entries from the previous configs
+                        let (inverse, expected) = (boxed.permutation.inverse(), table.permutation.inverse());
+                        assert!(table.permutation.forward().iter().all(|value| inverse[*value as usize] == expected[*value as usize]));
+                        assert!(boxed.eq(sequence));
+                    }
+                }
+
+                // continues from the current position, including an exhausted sequence
+                let mut sequence = RandomSequenceBuilder::<$type>::new(7, 11).into_iter();
+                sequence.set_index(42);
+                let values: Vec<$type> = sequence.materialize_into(&mut forward, &mut inverse).take(10).collect();
+                assert_eq!(values, sequence.clone().take(10).collect::<Vec<_>>());
+                sequence.set_index($type::MAX);
+                sequence.next();
+                assert!(sequence.materialize_into(&mut forward, &mut inverse).exhausted());
+            }
+        };
+    }
+
+    test_table!(test_u8_table, u8, 256);
+    test_table!(test_u16_table, u16, 65536);
+
+    #[test]
+    #[should_panic(expected = "isn't part of the sequence")]
+    fn test_table_missing() {
+        let sequence = RandomSequenceBuilder::<u8>::new(7, 11).with_domain(100).into_iter();
+        sequence.materialize_into(&mut [0; 256], &mut [0; 256]).index_of(100);
+    }
+}
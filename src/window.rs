@@ -0,0 +1,309 @@
+use core::ops::Range;
+
+use num_traits::{FromPrimitive, One, Zero};
+
+use crate::builder::to_u128;
+use crate::permutation::{BuilderPermutation, Permutation};
+use crate::sequence::{unsized_hint, RandomSequence};
+use crate::signed::SequenceInt;
+
+/// A double-ended iterator over the values of a window of indices of a [RandomSequence], built
+/// by [RandomSequence::window].
+///
+/// The window holds its own copy of the permutation and its own cursors at both ends, so it
+/// doesn't move the cursor of the sequence it was built from.
+///
+/// ```rust
+/// use rand_unique::RandomSequence;
+///
+/// let sequence = RandomSequence::<u64>::new(10, 2);
+/// let mut window = sequence.window(1000..1010);
+/// assert_eq!(window.len(), 10);
+/// assert_eq!(window.next(), Some(sequence.n(1000)));
+/// assert_eq!(window.next_back(), Some(sequence.n(1009)));
+/// assert_eq!(window.remaining(), 8);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Window<T, P = BuilderPermutation<<T as SequenceInt>::Unsigned>>
+where
+    T: SequenceInt
+{
+    permutation: P,
+
+    /// The next index from the front.
+    front: T::Unsigned,
+
+    /// One past the next index from the back, the window is exhausted once `front == back`.
+    back: T::Unsigned,
+}
+
+impl<T, P> RandomSequence<T, P>
+where
+    T: SequenceInt,
+    P: Permutation<T> + Clone,
+{
+    /// Iterate over the values at the indices in `range` from either end, the same values as
+    /// [RandomSequence::n] for each index, without moving the cursor of this sequence.
+    ///
+    /// The window reports its exact length, so a half-open window can hold every index of a `u64`
+    /// sequence but the last. Signed indices are ordered by their bit pattern, as for
+    /// [RandomSequence::set_index], so `-1` is the last index of the sequence.
+    ///
+    /// # Panics
+    ///
+    /// If the start of the range is after its end, or the range ends past the end of the sequence.
+    pub fn window(&self, range: Range<T>) -> Window<T, P> {
+        let (front, back) = (range.start.to_unsigned(), range.end.to_unsigned());
+        assert!(front <= back, "The start of the window must not be after its end.");
+        assert!(
+            back == T::Unsigned::zero() || back - T::Unsigned::one() <= self.permutation.max_index(),
+            "The window must be within the sequence."
+        );
+        Window {
+            permutation: self.permutation.clone(),
+            front,
+            back,
+        }
+    }
+}
+
+impl<T, P> Window<T, P>
+where
+    T: SequenceInt,
+    P: Permutation<T>,
+{
+    /// The number of values left in the window, which fits in a `u128` for every integer type.
+    #[inline]
+    pub fn remaining(&self) -> u128 {
+        to_u128(self.back - self.front)
+    }
+
+    /// Skip `n` values and get the next from the front, in O(1).
+    #[inline]
+    fn nth_front(&mut self, n: usize) -> Option<T> {
+        match T::Unsigned::from_usize(n) {
+            Some(n) if n < self.back - self.front => {
+                self.front = self.front + n;
+                self.next_front()
+            }
+            _ => {
+                self.front = self.back;
+                None
+            }
+        }
+    }
+
+    /// Skip `n` values and get the next from the back, in O(1).
+    #[inline]
+    fn nth_back_value(&mut self, n: usize) -> Option<T> {
+        match T::Unsigned::from_usize(n) {
+            Some(n) if n < self.back - self.front => {
+                self.back = self.back - n;
+                self.next_back_value()
+            }
+            _ => {
+                self.back = self.front;
+                None
+            }
+        }
+    }
+
+    /// Get the next value from the front.
+    #[inline]
+    fn next_front(&mut self) -> Option<T> {
+        if self.front == self.back {
+            return None;
+        }
+        let value = self.permutation.apply_sequential(self.front);
+        self.front = self.front + T::Unsigned::one();
+        Some(value)
+    }
+
+    /// Get the next value from the back.
+    #[inline]
+    fn next_back_value(&mut self) -> Option<T> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back = self.back - T::Unsigned::one();
+        Some(self.permutation.apply_sequential(self.back))
+    }
+}
+
+macro_rules! impl_window_iterator {
+    ($T:ident) => {
+        impl<P: Permutation<$T>> Iterator for Window<$T, P> {
+            type Item = $T;
+
+            #[inline]
+            fn next(&mut self) -> Option<Self::Item> {
+                self.next_front()
+            }
+
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                match self.remaining() {
+                    0 => (0, Some(0)),
+                    remaining => unsized_hint(remaining - 1, usize::MAX as u128),
+                }
+            }
+
+            #[inline]
+            fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                self.nth_front(n)
+            }
+
+            #[inline]
+            fn last(mut self) -> Option<Self::Item> {
+                self.next_back_value()
+            }
+
+            #[inline]
+            fn count(self) -> usize {
+                usize::try_from(self.remaining()).expect("The remaining length of the window doesn't fit in a usize.")
+            }
+        }
+
+        impl<P: Permutation<$T>> DoubleEndedIterator for Window<$T, P> {
+            #[inline]
+            fn next_back(&mut self) -> Option<Self::Item> {
+                self.next_back_value()
+            }
+
+            #[inline]
+            fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+                self.nth_back_value(n)
+            }
+        }
+    };
+    ($T:ident, exact) => {
+        impl_window_iterator!($T);
+
+        impl<P: Permutation<$T>> ExactSizeIterator for Window<$T, P> {}
+    };
+}
+
+// A half-open window holds at most `T::MAX` values, so fits exact size iterators in types up to
+// the width of usize, one more than a RandomSequence.
+impl_window_iterator!(u8, exact);
+impl_window_iterator!(u16, exact);
+impl_window_iterator!(u32, exact);
+#[cfg(target_pointer_width = "64")]
+impl_window_iterator!(u64, exact);
+#[cfg(target_pointer_width = "32")]
+impl_window_iterator!(u64);
+impl_window_iterator!(usize, exact);
+impl_window_iterator!(u128);
+impl_window_iterator!(i8, exact);
+impl_window_iterator!(i16, exact);
+impl_window_iterator!(i32, exact);
+#[cfg(target_pointer_width = "64")]
+impl_window_iterator!(i64, exact);
+#[cfg(target_pointer_width = "32")]
+impl_window_iterator!(i64);
+impl_window_iterator!(isize, exact);
+impl_window_iterator!(i128);
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::builder::{test_configs, RandomSequenceBuilder};
+
+    macro_rules! test_window {
+        ($name:ident, $type:ident, $unsigned:ident) => {
+            #[test]
+            fn $name() {
+                for config in test_configs::<$unsigned>() {
+                    let sequence = RandomSequence::<$type>::from(config);
+                    let len = (config.max_index() as u128).saturating_add(1).min(100) as $unsigned;
+                    let windows = [(0, len), (0, 0), (len / 2, len / 2), (len / 3, len), (len - 1, len), (0, len / 2)];
+                    for (start, end) in windows {
+                        let expected: Vec<$type> = (start..end).map(|i| sequence.n(i as $type)).collect();
+                        let window = sequence.window(start as $type..end as $type);
+                        assert_eq!(window.remaining(), (end - start) as u128);
+                        assert_eq!(window.size_hint(), (expected.len(), Some(expected.len())));
+                        assert_eq!(window.clone().count(), expected.len());
+                        assert_eq!(window.clone().last(), expected.last().copied());
+                        assert_eq!(window.clone().collect::<Vec<_>>(), expected);
+                        assert!(window.clone().rev().eq(expected.iter().rev().copied()));
+
+                        // both ends meet in the middle, without repeating a value
+                        let mut window = window;
+                        let mut values = Vec::new();
+                        while let Some(value) = window.next() {
+                            values.push(value);
+                            values.extend(window.next_back());
+                        }
+                        assert_eq!(window.remaining(), 0);
+                        assert_eq!((window.next(), window.next_back()), (None, None));
+                        values.sort();
+                        let mut sorted = expected.clone();
+                        sorted.sort();
+                        assert_eq!(values, sorted);
+
+                        // nth and nth_back jump in O(1), consistent with stepping
+                        for n in [0, 1, 2, 5, 50, 200] {
+                            let window = sequence.window(start as $type..end as $type);
+                            // the default nth of an adapter steps one value at a time
+                            let (mut fast, mut walk) = (window.clone(), window.clone().inspect(|_| ()));
+                            for _ in 0..3 {
+                                assert_eq!(fast.nth(n), walk.nth(n));
+                                assert_eq!(fast.nth_back(n), walk.nth_back(n));
+                                assert_eq!(fast.remaining(), walk.clone().count() as u128);
+                            }
+                            assert!(window.clone().step_by(n + 1).eq(expected.iter().copied().step_by(n + 1)));
+                            assert!(window.skip(n).eq(expected.iter().copied().skip(n)));
+                        }
+                    }
+                }
+
+                // the window doesn't move the cursor of the sequence
+                let mut sequence = RandomSequence::<$type>::new(7, 11);
+                sequence.next();
+                let far = ($unsigned::MAX / 3) as $type;
+                let mut window = sequence.window(far..far + (10 as $type));
+                assert_eq!(window.next(), Some(sequence.n(far)));
+                assert_eq!(window.next_back(), Some(sequence.n(far + (9 as $type))));
+                assert_eq!(sequence.index(), Some(1));
+
+                // the largest window
+                let window = sequence.window(0..(0 as $type).wrapping_sub(1));
+                assert_eq!(window.remaining(), $unsigned::MAX as u128);
+                assert_eq!(window.clone().next_back(), Some(sequence.n((0 as $type).wrapping_sub(2))));
+            }
+        };
+    }
+
+    test_window!(test_u8_window, u8, u8);
+    test_window!(test_u16_window, u16, u16);
+    test_window!(test_u32_window, u32, u32);
+    test_window!(test_u64_window, u64, u64);
+    test_window!(test_usize_window, usize, usize);
+    test_window!(test_u128_window, u128, u128);
+    test_window!(test_i8_window, i8, u8);
+    test_window!(test_i64_window, i64, u64);
+
+    #[test]
+    fn test_window_hint() {
+        let sequence = RandomSequence::<u64>::new(7, 11);
+        assert_eq!(sequence.window(0..u64::MAX).len(), u64::MAX as usize);
+        let sequence = RandomSequence::<u128>::new(7, 11);
+        assert_eq!(sequence.window(0..u128::MAX).size_hint(), (usize::MAX, None));
+        assert_eq!(sequence.window(5..1005).size_hint(), (1000, Some(1000)));
+    }
+
+    #[test]
+    #[should_panic(expected = "must be within the sequence")]
+    fn test_window_past_end() {
+        RandomSequenceBuilder::<u32>::new(7, 11).with_domain(100).into_iter().window(0..101);
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be after its end")]
+    fn test_window_reversed() {
+        let (start, end) = (10, 5);
+        RandomSequence::<u32>::new(7, 11).window(start..end);
+    }
+}